@@ -1,5 +1,5 @@
 #![allow(dead_code)]
-use crate::render::ReflectionConstants;
+use crate::render::{DepthCompare, ReflectionConstants};
 
 /* CONFIG */
 pub const DEFAULT_SCRIPT: &str = "scripts/stonehenge.mdl";
@@ -7,12 +7,34 @@ pub const DEFAULT_PICTURE_DIMENSIONS: (usize, usize) = (500, 500);
 pub const DEFAULT_BACKGROUND_COLOR: (usize, usize, usize) = WHITE;
 pub const DEFAULT_FOREGROUND_COLOR: (usize, usize, usize) = BLUE;
 pub const PARAMETRIC_STEPS: i32 = 20;
-pub const ENABLE_BACK_FACE_CULLING: bool = true;
-pub const ENABLE_Z_BUFFER: bool = true;
+pub const MIN_PARAMETRIC_STEPS: i32 = 3;
+pub const MAX_PARAMETRIC_STEPS: i32 = 200;
+pub const DEFAULT_ADAPTIVE_SEGMENT_LENGTH: f32 = 10.0; // pixels
+#[derive(Clone, Copy, Debug)]
+pub enum Tessellation {
+    Fixed(i32),
+    Adaptive(f32), // how long each segment should be on screen
+}
+pub const DEFAULT_TESSELLATION: Tessellation = Tessellation::Fixed(PARAMETRIC_STEPS);
+pub const DEFAULT_ICOSPHERE_SUBDIVISIONS: usize = 2;
+pub const MAX_ICOSPHERE_SUBDIVISIONS: usize = 6;
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CullMode {
+    Back,
+    Front,
+    None, // draws both sides, for open meshes like terrain that would have holes
+}
+pub const DEFAULT_CULL_MODE: CullMode = CullMode::Back;
+pub const DEFAULT_TWO_SIDED_LIGHTING: bool = false;
+pub const DEFAULT_Z_TEST: bool = true;
+pub const DEFAULT_Z_WRITE: bool = true;
+pub const DEFAULT_Z_COMPARE: DepthCompare = DepthCompare::CloserOrEqual;
+pub const DEFAULT_PNG_COMPRESSION: png::Compression = png::Compression::Fast;
 pub const DEFAULT_REFLECTION_CONSTANTS: ReflectionConstants = ReflectionConstants {
     ambient: [0.2, 0.2, 0.2],
     diffuse: [0.5, 0.5, 0.5],
     specular: [0.5, 0.5, 0.5],
+    opacity: 1.0,
 };
 #[derive(Clone, Copy, Debug)]
 pub enum ShadingMode {
@@ -21,11 +43,23 @@ pub enum ShadingMode {
     Flat,
     Gouraud,
     Phong,
+    Toon(usize), // phong but the light is split into this many bands
+    // debug modes, these ignore lighting
+    Normals,
+    Depth,
+    UvChecker,
+    WireframeShaded,
+    BackFaces, // flat but the faces that would get culled are drawn in red
 }
 pub const DEFAULT_SHADING_MODE: ShadingMode = ShadingMode::Flat;
+pub const DEFAULT_TOON_BANDS: usize = 4;
+pub const UV_CHECKER_SQUARES: f32 = 8.0; // across the whole 0 to 1 range of a uv
+pub const DEFAULT_CREASE_ANGLE: f32 = 180.0; // degrees, 180 smooths across every edge
+pub const SWEEP_CORNER_ANGLE: f32 = 45.0; // degrees, extrude outlines that turn more than this at a point get a hard edge there
 pub const SPECULAR_EXPONENT: f32 = 5.0;
 pub const GENERATE_TEMPORARY_FRAME_FILES: bool = true;
-pub const DEFAULT_ANIMATION_DELAY_MS: u32 = 20; // for some reason when this is set to 10 ms it becomes really slow
+pub const CACHE_TEMPORARY_FRAME_FILES: bool = true; // only render frames in temp_frames again if something they use changed
+pub const DEFAULT_FRAME_RATE: f32 = 50.0; // 20 ms per frame, for some reason when the gif delay is set to 10 ms it becomes really slow
 
 /* COLORS */
 pub const WHITE: (usize, usize, usize) = (255, 255, 255);
@@ -50,6 +84,18 @@ pub const BEZIER: [[f32; 4]; 4] = [
     [-3.0, 3.0, 0.0, 0.0],
     [1.0, 0.0, 0.0, 0.0],
 ];
+pub const CATMULL_ROM: [[f32; 4]; 4] = [
+    [-0.5, 1.0, -0.5, 0.0],
+    [1.5, -2.5, 0.0, 1.0],
+    [-1.5, 2.0, 0.5, 0.0],
+    [0.5, -0.5, 0.0, 0.0],
+];
+pub const B_SPLINE: [[f32; 4]; 4] = [
+    [-1.0 / 6.0, 0.5, -0.5, 1.0 / 6.0],
+    [0.5, -1.0, 0.0, 4.0 / 6.0],
+    [-0.5, 0.5, 0.5, 1.0 / 6.0],
+    [1.0 / 6.0, 0.0, 0.0, 0.0],
+];
 pub const CUBE: [(usize, usize, usize); 12] = [
     (0, 2, 1),
     (0, 3, 2),
@@ -64,3 +110,25 @@ pub const CUBE: [(usize, usize, usize); 12] = [
     (7, 5, 6),
     (7, 4, 5),
 ];
+pub const ICOSAHEDRON: [(usize, usize, usize); 20] = [
+    (0, 11, 5),
+    (0, 5, 1),
+    (0, 1, 7),
+    (0, 7, 10),
+    (0, 10, 11),
+    (1, 5, 9),
+    (5, 11, 4),
+    (11, 10, 2),
+    (10, 7, 6),
+    (7, 1, 8),
+    (3, 9, 4),
+    (3, 4, 2),
+    (3, 2, 6),
+    (3, 6, 8),
+    (3, 8, 9),
+    (4, 9, 5),
+    (2, 4, 11),
+    (6, 2, 10),
+    (8, 6, 7),
+    (9, 8, 1),
+];
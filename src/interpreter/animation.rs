@@ -1,13 +1,16 @@
-use super::parser::Command;
+use super::{
+    expression::noise,
+    parser::{Command, Easing, Interpolation, Keyframe, LayerMode, Parameter, Time, Wave},
+};
 use std::{
+    f32::consts::PI,
     sync::LazyLock,
     error::Error,
     collections::HashMap,
     fs::File
 };
-use image::{ImageBuffer, RgbaImage, Frame, Delay, codecs::gif::{GifEncoder, Repeat}};
 use crate::{
-    constants::BEZIER,
+    constants::{BEZIER, DEFAULT_FRAME_RATE},
     matrix::multiply,
 };
 
@@ -54,20 +57,204 @@ impl CubicBezierEasing {
     }
 }
 
-static EASING_FUNCTIONS: LazyLock<HashMap<&str, CubicBezierEasing>> = LazyLock::new(|| {
+enum EasingFunction {
+    CubicBezier(CubicBezierEasing),
+    Formula(fn(f32) -> f32),
+}
+
+impl EasingFunction {
+    fn eval(&self, t: f32) -> f32 {
+        match self {
+            EasingFunction::CubicBezier(bezier) => bezier.eval(t),
+            EasingFunction::Formula(formula) => formula(t),
+        }
+    }
+}
+
+// the formulas are from easings.net, they all take t from 0 to 1 and give 0 at t = 0 and 1 at t = 1
+// back and elastic go past 0 and 1 in the middle on purpose
+static EASING_FUNCTIONS: LazyLock<HashMap<&str, EasingFunction>> = LazyLock::new(|| {
+    use EasingFunction::{CubicBezier, Formula};
+
+    const BACK: f32 = 1.70158;
+    const BACK_IN_OUT: f32 = BACK * 1.525;
+    const ELASTIC: f32 = 2.0 * PI / 3.0;
+    const ELASTIC_IN_OUT: f32 = 2.0 * PI / 4.5;
+
     let mut map = HashMap::new();
 
-    map.insert("easeInCubic", CubicBezierEasing::new(0.33, 0.0, 0.68, 1.0));
-    map.insert("easeOutCubic", CubicBezierEasing::new(0.33, 1.0, 0.68, 1.0));
-    map.insert("easeInExpo", CubicBezierEasing::new(0.7, 0.0, 0.84, 0.0));
-    map.insert("easeOutExpo", CubicBezierEasing::new(0.16, 1.0, 0.3, 1.0));
+    // same as the css keywords
+    map.insert("ease", CubicBezier(CubicBezierEasing::new(0.25, 0.1, 0.25, 1.0)));
+    map.insert("easeIn", CubicBezier(CubicBezierEasing::new(0.42, 0.0, 1.0, 1.0)));
+    map.insert("easeOut", CubicBezier(CubicBezierEasing::new(0.0, 0.0, 0.58, 1.0)));
+    map.insert("easeInOut", CubicBezier(CubicBezierEasing::new(0.42, 0.0, 0.58, 1.0)));
+
+    map.insert("easeInSine", Formula(|t| 1.0 - (t * PI / 2.0).cos()));
+    map.insert("easeOutSine", Formula(|t| (t * PI / 2.0).sin()));
+    map.insert("easeInOutSine", Formula(|t| -((PI * t).cos() - 1.0) / 2.0));
+
+    map.insert("easeInQuad", Formula(|t| t.powi(2)));
+    map.insert("easeOutQuad", Formula(|t| 1.0 - (1.0 - t).powi(2)));
+    map.insert("easeInOutQuad", Formula(|t| in_out(t, |t| t.powi(2))));
+
+    map.insert("easeInCubic", Formula(|t| t.powi(3)));
+    map.insert("easeOutCubic", Formula(|t| 1.0 - (1.0 - t).powi(3)));
+    map.insert("easeInOutCubic", Formula(|t| in_out(t, |t| t.powi(3))));
+
+    map.insert("easeInQuart", Formula(|t| t.powi(4)));
+    map.insert("easeOutQuart", Formula(|t| 1.0 - (1.0 - t).powi(4)));
+    map.insert("easeInOutQuart", Formula(|t| in_out(t, |t| t.powi(4))));
+
+    map.insert("easeInQuint", Formula(|t| t.powi(5)));
+    map.insert("easeOutQuint", Formula(|t| 1.0 - (1.0 - t).powi(5)));
+    map.insert("easeInOutQuint", Formula(|t| in_out(t, |t| t.powi(5))));
+
+    map.insert("easeInExpo", Formula(ease_in_expo));
+    map.insert("easeOutExpo", Formula(|t| 1.0 - ease_in_expo(1.0 - t)));
+    map.insert("easeInOutExpo", Formula(|t| in_out(t, ease_in_expo)));
+
+    map.insert("easeInCirc", Formula(ease_in_circ));
+    map.insert("easeOutCirc", Formula(|t| 1.0 - ease_in_circ(1.0 - t)));
+    map.insert("easeInOutCirc", Formula(|t| in_out(t, ease_in_circ)));
+
+    map.insert("easeInBack", Formula(|t| (BACK + 1.0) * t.powi(3) - BACK * t.powi(2)));
+    map.insert("easeOutBack", Formula(|t| 1.0 + (BACK + 1.0) * (t - 1.0).powi(3) + BACK * (t - 1.0).powi(2)));
+    map.insert("easeInOutBack", Formula(|t| in_out(t, |t| (BACK_IN_OUT + 1.0) * t.powi(3) - BACK_IN_OUT * t.powi(2))));
+
+    map.insert("easeInElastic", Formula(|t| {
+        if t <= 0.0 || t >= 1.0 { return t.clamp(0.0, 1.0); }
+        -(2.0_f32).powf(10.0 * t - 10.0) * ((t * 10.0 - 10.75) * ELASTIC).sin()
+    }));
+    map.insert("easeOutElastic", Formula(|t| {
+        if t <= 0.0 || t >= 1.0 { return t.clamp(0.0, 1.0); }
+        (2.0_f32).powf(-10.0 * t) * ((t * 10.0 - 0.75) * ELASTIC).sin() + 1.0
+    }));
+    map.insert("easeInOutElastic", Formula(|t| {
+        if t <= 0.0 || t >= 1.0 { return t.clamp(0.0, 1.0); }
+        let wave = ((20.0 * t - 11.125) * ELASTIC_IN_OUT).sin();
+        if t < 0.5 {
+            -(2.0_f32).powf(20.0 * t - 10.0) * wave / 2.0
+        } else {
+            (2.0_f32).powf(-20.0 * t + 10.0) * wave / 2.0 + 1.0
+        }
+    }));
+
+    map.insert("easeInBounce", Formula(|t| 1.0 - ease_out_bounce(1.0 - t)));
+    map.insert("easeOutBounce", Formula(ease_out_bounce));
+    map.insert("easeInOutBounce", Formula(|t| in_out(t, |t| 1.0 - ease_out_bounce(1.0 - t))));
 
     map
 });
 
-pub fn first_pass(commands: &Vec<Command>) -> Result<(usize, String), Box<dyn Error>> {
+fn in_out(t: f32, ease_in: fn(f32) -> f32) -> f32 {
+    // the first half is the ease in sped up and the second half is the same thing flipped around
+    if t < 0.5 {
+        ease_in(2.0 * t) / 2.0
+    } else {
+        1.0 - ease_in(2.0 - 2.0 * t) / 2.0
+    }
+}
+
+fn ease_in_expo(t: f32) -> f32 {
+    if t <= 0.0 { 0.0 } else { (2.0_f32).powf(10.0 * t - 10.0) }
+}
+
+fn ease_in_circ(t: f32) -> f32 {
+    1.0 - (1.0 - t.powi(2)).max(0.0).sqrt()
+}
+
+fn ease_out_bounce(t: f32) -> f32 {
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+fn ease(easing: &Option<Easing>, t: f32) -> Result<f32, Box<dyn Error>> {
+    match easing {
+        None => Ok(t),
+        Some(Easing::Named(name)) => {
+            let function = EASING_FUNCTIONS.get(name.as_str())
+                .ok_or_else(|| format!("Easing function {} not recognized.", name))?;
+            Ok(function.eval(t))
+        }
+        Some(Easing::CubicBezier { x1, y1, x2, y2 }) => Ok(CubicBezierEasing::new(*x1, *y1, *x2, *y2).eval(t)),
+        // jumps at the end of each step like the css default so it only reaches 1 at the very end
+        Some(Easing::Steps(steps)) => Ok((t * *steps as f32).floor() / *steps as f32),
+    }
+}
+
+fn interpolate(start_val: f32, end_val: f32, start_frame: usize, end_frame: usize, frame: usize, easing: &Option<Easing>) -> Result<f32, Box<dyn Error>> {
+    // the easing gets the normalized time (0 to 1) and the result is how far to go from start_val to end_val
+    let t = if end_frame == start_frame {
+        1.0
+    } else {
+        (frame - start_frame) as f32 / (end_frame - start_frame) as f32
+    };
+
+    Ok(start_val + (end_val - start_val) * ease(easing, t)?)
+}
+
+// which frames actually get rendered, from render_frames/render_frame/render_every or --frames/--frame/--every
+// the knobs are still worked out for every frame so the ones that get rendered look the same as in the full animation
+#[derive(Clone, Copy, Default)]
+pub struct FrameRange {
+    pub frames: Option<(usize, usize)>,
+    pub every: Option<usize>,
+}
+
+impl FrameRange {
+    pub fn or(self, other: FrameRange) -> FrameRange {
+        // anything set here wins (the command line over the script)
+        FrameRange {
+            frames: self.frames.or(other.frames),
+            every: self.every.or(other.every),
+        }
+    }
+
+    pub fn selected(&self, num_frames: usize) -> Result<Vec<usize>, Box<dyn Error>> {
+        let (start, end) = self.frames.unwrap_or((0, num_frames - 1));
+
+        if start > end {
+            return Err(format!("Frame range {}-{} ends before it starts.", start, end).into());
+        }
+
+        if end >= num_frames {
+            return Err(format!("Frame {} is out of range, the animation only has frames 0-{}.", end, num_frames - 1).into());
+        }
+
+        Ok((start..=end).step_by(self.every.unwrap_or(1)).collect())
+    }
+}
+
+pub struct AnimationSettings {
+    pub num_frames: usize,
+    pub basename: String,
+    pub fps: f32,
+    // (samples, shutter angle in degrees), None means every frame is rendered once like normal
+    pub motion_blur: Option<(usize, f32)>,
+    pub frame_range: FrameRange,
+}
+
+pub fn first_pass(commands: &Vec<Command>) -> Result<AnimationSettings, Box<dyn Error>> {
     let mut frames: usize = 0;
     let mut basename = String::new();
+    let mut fps = DEFAULT_FRAME_RATE;
+    let mut duration: Option<f32> = None;
+    let mut motion_blur: Option<(usize, f32)> = None;
+    let mut render_frames: Option<(Time, Time)> = None;
+    let mut render_every: Option<usize> = None;
 
     let mut contains_frames = false;
     let mut contains_vary = false;
@@ -79,29 +266,68 @@ pub fn first_pass(commands: &Vec<Command>) -> Result<(usize, String), Box<dyn Er
             Command::SetBaseName { name } => { basename = name.clone(); contains_basename = true; }
             Command::Tween { .. } => { contains_tween = true; }
             Command::SetFrames { num_frames } => { frames = *num_frames; contains_frames = true; }
-            Command::VaryKnob { .. } => { contains_vary = true; }
+            Command::SetFrameRate { fps: rate } => { fps = *rate; }
+            Command::SetDuration { seconds } => { duration = Some(*seconds); }
+            Command::SetMotionBlur { samples, shutter_angle } => { motion_blur = Some((*samples, *shutter_angle)); }
+            Command::RenderFrames { start, end } => { render_frames = Some((*start, *end)); }
+            Command::RenderEvery { every } => { render_every = Some(*every); }
+            Command::VaryKnob { .. } | Command::Keyframes { .. } | Command::Wiggle { .. } | Command::Oscillate { .. } |
+            Command::LoopKnob { .. } | Command::KnobExpression { .. } | Command::Layer { .. } => { contains_vary = true; }
             _ => {}
         }
     }
 
+    // the duration is turned into frames here so everything after this only has to deal with frames
+    if let Some(seconds) = duration {
+        if contains_frames {
+            return Err("Both frames and duration were set, please only use one.".into());
+        }
+
+        frames = ((seconds * fps).round() as usize).max(1);
+        contains_frames = true;
+    }
+
     if (contains_vary || contains_tween || contains_basename) && !contains_frames {
-        Err("Animation was detected but the number of frames wasn't set.".into())
-    } else if contains_frames && !contains_basename {
-        println!("Number of frames was set but basename wasn't. 'frame' was chosen by default.");
-        Ok((frames, String::from("frame")))
+        Err("Animation was detected but the number of frames (or duration) wasn't set.".into())
     } else {
-        Ok((frames, basename))
+        if contains_frames && !contains_basename {
+            println!("Number of frames was set but basename wasn't. 'frame' was chosen by default.");
+            basename = String::from("frame");
+        }
+
+        let frame_range = FrameRange {
+            frames: render_frames.map(|(start, end)| (start.to_frame(fps), end.to_frame(fps))),
+            every: render_every,
+        };
+
+        Ok(AnimationSettings { num_frames: frames, basename, fps, motion_blur, frame_range })
     }
 }
 
-pub fn second_pass(commands: &Vec<Command>, frames: &usize) -> Result<Vec<HashMap<String, f32>>, Box<dyn Error>> {
-    let mut frame_knobs: Vec<HashMap<String, f32>> = vec![HashMap::new(); *frames];
-    let mut saved_knobs: HashMap<String, HashMap<String, f32>> = HashMap::new();
+pub fn second_pass(commands: &Vec<Command>, frames: &usize, fps: f32) -> Result<Vec<HashMap<String, f32>>, Box<dyn Error>> {
+    // times given in seconds get turned into frames with the frame rate from the first pass
+    knob_table(commands, *frames, fps, &mut HashMap::new())
+}
+
+fn knob_table(commands: &Vec<Command>, frames: usize, fps: f32, saved_knobs: &mut HashMap<String, HashMap<String, f32>>) -> Result<Vec<HashMap<String, f32>>, Box<dyn Error>> {
+    // layers get their own table made the same way, saved knob lists are shared with them
+    let mut frame_knobs: Vec<HashMap<String, f32>> = vec![HashMap::new(); frames];
+    // knobs from set so they can be saved in knob lists like in the original mdl
+    let mut set_knobs: HashMap<String, f32> = HashMap::new();
+
+    // the first and last frame each knob is animated in, loop and pingpong repeat what's in between
+    let mut animated_ranges: HashMap<String, (usize, usize)> = HashMap::new();
+    let mut mark_animated = |knob: &String, start: usize, end: usize| {
+        let range = animated_ranges.entry(knob.clone()).or_insert((start, end));
+        *range = (range.0.min(start), range.1.max(end));
+    };
 
     for command in commands {
         match command {
             Command::VaryKnob { knob, start_frame, end_frame, start_val, end_val, easing } => {
-                if *start_frame >= *frames || *end_frame >= *frames {
+                let (start_frame, end_frame) = (&start_frame.to_frame(fps), &end_frame.to_frame(fps));
+
+                if *start_frame >= frames || *end_frame >= frames {
                     return Err(format!("Vary command has frames outside range: {} to {}.", start_frame, end_frame).into());
                 }
                 
@@ -109,32 +335,69 @@ pub fn second_pass(commands: &Vec<Command>, frames: &usize) -> Result<Vec<HashMa
                     return Err(format!("Vary command has start_frame > end_frame: {} > {}.", start_frame, end_frame).into());
                 }
 
-                let num_frames = (end_frame - start_frame) as f32;
-                let delta = (end_val - start_val) / num_frames;
-
                 for frame in *start_frame..=*end_frame {
-                    let x = start_val + delta * ((frame - start_frame) as f32);
-                    
-                    if let Some(easing) = easing {
-                        if let Some(func) = EASING_FUNCTIONS.get(easing.as_str()) {
-                            frame_knobs[frame].insert(knob.clone(), func.eval(x));
-                        } else {
-                            return Err(format!("Easing function {} not recognized.", easing).into());
-                        }
-                    } else {
-                        frame_knobs[frame].insert(knob.clone(), x);
-                    }
+                    let value = interpolate(*start_val, *end_val, *start_frame, *end_frame, frame, easing)?;
+                    frame_knobs[frame].insert(knob.clone(), value);
+                }
+
+                mark_animated(knob, *start_frame, *end_frame);
+            }
+
+            Command::Keyframes { knob, keys } => {
+                let key_frames: Vec<usize> = keys.iter().map(|key| key.frame.to_frame(fps)).collect();
+
+                if let Some(frame) = key_frames.iter().find(|frame| **frame >= frames) {
+                    return Err(format!("Keyframes for {} has a frame outside range: {}.", knob, frame).into());
                 }
+
+                if let Some(pair) = key_frames.windows(2).find(|pair| pair[0] >= pair[1]) {
+                    return Err(format!("Keyframes for {} must be in order of frame: {} comes after {}.", knob, pair[1], pair[0]).into());
+                }
+
+                for (frame, knobs) in frame_knobs.iter_mut().enumerate() {
+                    knobs.insert(knob.clone(), keyframe_value(keys, frame, fps));
+                }
+
+                mark_animated(knob, key_frames[0], key_frames[key_frames.len() - 1]);
+            }
+
+            Command::SetKnob { name, value } => {
+                set_knobs.insert(name.clone(), *value);
+            }
+
+            Command::SaveKnobList { name, frame } => {
+                let frame = frame.map_or(0, |frame| frame.to_frame(fps));
+
+                if frame >= frames {
+                    return Err(format!("Knoblist '{}' is saved from a frame outside range: {}.", name, frame).into());
+                }
+
+                let mut knobs = frame_knobs[frame].clone();
+                knobs.extend(set_knobs.iter().map(|(knob, value)| (knob.clone(), *value)));
+                saved_knobs.insert(name.clone(), knobs);
             }
 
-            Command::SaveKnobList { name } => {
-                if !frame_knobs.is_empty() {
-                    saved_knobs.insert(name.clone(), frame_knobs[0].clone());
+            Command::BlendKnobs { name, lists } => {
+                // a weighted average of the lists, a knob missing from a list counts as 0 there like in tween
+                let total: f32 = lists.iter().map(|(_, weight)| weight).sum();
+                let mut blended: HashMap<String, f32> = HashMap::new();
+
+                for (list, weight) in lists {
+                    let knobs = saved_knobs.get(list)
+                        .ok_or_else(|| format!("Knoblist '{}' not found", list))?;
+
+                    for (knob, value) in knobs {
+                        *blended.entry(knob.clone()).or_insert(0.0) += value * weight / total;
+                    }
                 }
+
+                saved_knobs.insert(name.clone(), blended);
             }
 
             Command::Tween { start_frame, end_frame, knoblist0, knoblist1, easing } => {
-                if *start_frame >= *frames || *end_frame >= *frames {
+                let (start_frame, end_frame) = (&start_frame.to_frame(fps), &end_frame.to_frame(fps));
+
+                if *start_frame >= frames || *end_frame >= frames {
                     return Err(format!("Tween command has frames outside range: {} to {}.", start_frame, end_frame).into());
                 }
                 
@@ -147,8 +410,6 @@ pub fn second_pass(commands: &Vec<Command>, frames: &usize) -> Result<Vec<HashMa
                 let knobs1 = saved_knobs.get(knoblist1)
                     .ok_or_else(|| format!("Knoblist '{}' not found", knoblist1))?;
 
-                let num_frames = (end_frame - start_frame) as f32;
-
                 let mut all_knobs: std::collections::HashSet<String> = std::collections::HashSet::new();
                 for knob in knobs0.keys() {
                     all_knobs.insert(knob.clone());
@@ -160,20 +421,13 @@ pub fn second_pass(commands: &Vec<Command>, frames: &usize) -> Result<Vec<HashMa
                 for knob in all_knobs {
                     let start_val = *knobs0.get(&knob).unwrap_or(&0.0);
                     let end_val = *knobs1.get(&knob).unwrap_or(&0.0);
-                    let delta = (end_val - start_val) / num_frames;
+
                     for frame in *start_frame..=*end_frame {
-                        let x = start_val + delta * ((frame - start_frame) as f32);
-                        
-                        if let Some(easing) = easing {
-                            if let Some(func) = EASING_FUNCTIONS.get(easing.as_str()) {
-                                frame_knobs[frame].insert(knob.clone(), func.eval(x));
-                            } else {
-                                return Err(format!("Easing function {} not recognized.", easing).into());
-                            }
-                        } else {
-                            frame_knobs[frame].insert(knob.clone(), x);
-                        }
+                        let value = interpolate(start_val, end_val, *start_frame, *end_frame, frame, easing)?;
+                        frame_knobs[frame].insert(knob.clone(), value);
                     }
+
+                    mark_animated(&knob, *start_frame, *end_frame);
                 }
             }
 
@@ -181,9 +435,227 @@ pub fn second_pass(commands: &Vec<Command>, frames: &usize) -> Result<Vec<HashMa
         }
     }
 
+    hold_knobs(&mut frame_knobs);
+    apply_loops(commands, &mut frame_knobs, &animated_ranges)?;
+    apply_drivers(commands, &mut frame_knobs, fps)?;
+    apply_layers(commands, &mut frame_knobs, fps, saved_knobs)?;
+
     Ok(frame_knobs)
 }
 
+fn apply_layers(commands: &Vec<Command>, frame_knobs: &mut [HashMap<String, f32>], fps: f32, saved_knobs: &mut HashMap<String, HashMap<String, f32>>) -> Result<(), Box<dyn Error>> {
+    // each layer is animated on its own and then put on top of everything before it, in the order they're written
+    // add puts the layer's values on top of what's there and blend moves what's there weight of the way to the layer's values
+    // if the layer has a mask only the knobs in it are touched
+    for command in commands {
+        if let Command::Layer { mode, mask, commands } = command {
+            let layer = knob_table(commands, frame_knobs.len(), fps, saved_knobs)?;
+
+            for (frame, (knobs, layer_knobs)) in frame_knobs.iter_mut().zip(layer).enumerate() {
+                let weight = match mode {
+                    LayerMode::Add => 1.0,
+                    LayerMode::Blend(weight) => frame_parameter(weight, knobs, frame, fps)?,
+                };
+
+                for (knob, value) in layer_knobs {
+                    if !mask.is_empty() && !mask.contains(&knob) {
+                        continue;
+                    }
+
+                    let current = knobs.entry(knob).or_insert(0.0);
+                    *current = match mode {
+                        LayerMode::Add => *current + value,
+                        LayerMode::Blend(_) => *current + (value - *current) * weight,
+                    };
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn frame_parameter(parameter: &Parameter, knobs: &HashMap<String, f32>, frame: usize, fps: f32) -> Result<f32, Box<dyn Error>> {
+    // the same as a parameter while rendering but with the knobs from the table since nothing is rendering yet
+    match parameter {
+        Parameter::Number(value) => Ok(*value),
+        Parameter::Knob(name) => knobs.get(name).copied().ok_or_else(|| format!("Knob {} not found.", name).into()),
+        Parameter::Expression(expression) => expression.eval(&|name| match name {
+            "frame" => Some(frame as f32),
+            "time" => Some(frame as f32 / fps),
+            "fps" => Some(fps),
+            _ => knobs.get(name).copied(),
+        }),
+    }
+}
+
+fn apply_loops(commands: &Vec<Command>, frame_knobs: &mut [HashMap<String, f32>], animated_ranges: &HashMap<String, (usize, usize)>) -> Result<(), Box<dyn Error>> {
+    // outside of the frames a knob is animated in, repeat the animation instead of holding the last value
+    // loop starts over from the beginning every time and pingpong plays it backwards every other time
+    for command in commands {
+        if let Command::LoopKnob { knob, pingpong } = command {
+            let (first, last) = *animated_ranges.get(knob)
+                .ok_or_else(|| format!("Can't loop {} since it isn't animated with vary, tween, or keyframes.", knob))?;
+            let length = (last - first) as isize;
+
+            if length == 0 {
+                continue;
+            }
+
+            let animation: Vec<f32> = (first..=last).map(|frame| frame_knobs[frame][knob]).collect();
+
+            for (frame, knobs) in frame_knobs.iter_mut().enumerate() {
+                if (first..=last).contains(&frame) {
+                    continue;
+                }
+
+                let offset = frame as isize - first as isize;
+                let index = if *pingpong {
+                    let position = offset.rem_euclid(2 * length);
+                    if position <= length { position } else { 2 * length - position }
+                } else {
+                    offset.rem_euclid(length)
+                };
+
+                knobs.insert(knob.clone(), animation[index as usize]);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn apply_drivers(commands: &Vec<Command>, frame_knobs: &mut [HashMap<String, f32>], fps: f32) -> Result<(), Box<dyn Error>> {
+    // drivers are worked out for every frame after everything else so they can build on top of vary, tween, and keyframes
+    // wiggle and oscillate add to whatever the knob already is (0 if it isn't animated) and expressions replace it
+    for command in commands {
+        match command {
+            Command::Wiggle { knob, frequency, amplitude, seed } => {
+                for (frame, knobs) in frame_knobs.iter_mut().enumerate() {
+                    let time = frame as f32 / fps;
+                    *knobs.entry(knob.clone()).or_insert(0.0) += amplitude * noise(time * frequency, *seed);
+                }
+            }
+
+            Command::Oscillate { knob, wave, period, amplitude, phase } => {
+                let period = period.to_frames_exact(fps);
+                if period <= 0.0 {
+                    return Err(format!("Oscillate period for {} must be positive.", knob).into());
+                }
+
+                for (frame, knobs) in frame_knobs.iter_mut().enumerate() {
+                    // how many cycles in we are, phase is a fraction of a cycle
+                    let cycles = frame as f32 / period + phase;
+                    let value = match wave {
+                        Wave::Sine => (2.0 * PI * cycles).sin(),
+                        Wave::Triangle => 4.0 * ((cycles - 0.25).rem_euclid(1.0) - 0.5).abs() - 1.0,
+                        Wave::Square => if cycles.rem_euclid(1.0) < 0.5 { 1.0 } else { -1.0 },
+                        Wave::Saw => 2.0 * (cycles + 0.5).rem_euclid(1.0) - 1.0,
+                    };
+
+                    *knobs.entry(knob.clone()).or_insert(0.0) += amplitude * value;
+                }
+            }
+
+            Command::KnobExpression { knob, expression } => {
+                for (frame, knobs) in frame_knobs.iter_mut().enumerate() {
+                    let value = expression.eval(&|name| match name {
+                        "frame" => Some(frame as f32),
+                        "time" => Some(frame as f32 / fps),
+                        "fps" => Some(fps),
+                        "value" => Some(knobs.get(knob).copied().unwrap_or(0.0)),
+                        _ => knobs.get(name).copied(),
+                    })?;
+
+                    knobs.insert(knob.clone(), value);
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn hold_knobs(frame_knobs: &mut [HashMap<String, f32>]) {
+    // frames that a knob isn't animated in keep the closest value it does have
+    // after its last frame it holds the last value and before its first frame it already sits at the first one
+    let mut last_values: HashMap<String, f32> = HashMap::new();
+
+    for knobs in frame_knobs.iter_mut() {
+        for (knob, value) in &last_values {
+            knobs.entry(knob.clone()).or_insert(*value);
+        }
+        last_values = knobs.clone();
+    }
+
+    for knobs in frame_knobs.iter_mut().rev() {
+        for (knob, value) in &last_values {
+            knobs.entry(knob.clone()).or_insert(*value);
+        }
+        last_values = knobs.clone();
+    }
+}
+
+fn keyframe_value(keys: &[Keyframe], frame: usize, fps: f32) -> f32 {
+    // outside of the keys the knob holds the first or last value
+    let key_frame = |i: usize| keys[i].frame.to_frame(fps);
+    let next = (0..keys.len()).position(|i| key_frame(i) > frame);
+
+    let i = match next {
+        Some(0) => return keys[0].value,
+        Some(next) => next - 1,
+        None => return keys[keys.len() - 1].value,
+    };
+
+    let (key0, key1) = (&keys[i], &keys[i + 1]);
+    let duration = (key_frame(i + 1) - key_frame(i)) as f32;
+    let t = (frame - key_frame(i)) as f32 / duration;
+
+    match key0.interpolation {
+        Interpolation::Step => key0.value,
+        Interpolation::Linear => key0.value + (key1.value - key0.value) * t,
+        Interpolation::Bezier { x1, y1, x2, y2 } => {
+            // same as a css cubic-bezier, x is time and y is how far between the two values
+            key0.value + (key1.value - key0.value) * CubicBezierEasing::new(x1, y1, x2, y2).eval(t)
+        }
+        Interpolation::CatmullRom => {
+            // cubic hermite where the slope at each key points from the key before it to the key after it
+            // the slopes are per frame and then scaled by the length of the segment so uneven spacing between keys still works
+            let slope = |i: usize| {
+                let (before, after) = (i.saturating_sub(1), (i + 1).min(keys.len() - 1));
+                (keys[after].value - keys[before].value) / (key_frame(after) - key_frame(before)) as f32
+            };
+            let (m0, m1) = (slope(i) * duration, slope(i + 1) * duration);
+
+            let t2 = t * t;
+            let t3 = t2 * t;
+
+            (2.0 * t3 - 3.0 * t2 + 1.0) * key0.value
+                + (t3 - 2.0 * t2 + t) * m0
+                + (-2.0 * t3 + 3.0 * t2) * key1.value
+                + (t3 - t2) * m1
+        }
+    }
+}
+
+pub fn sample_knobs(frame_knobs: &[HashMap<String, f32>], frame: usize, offset: f32) -> HashMap<String, f32> {
+    // knob values part of the way from this frame to the next one, used for the motion blur samples
+    // the last frame doesn't have a next one so it keeps going the way it was going from the frame before
+    let current = &frame_knobs[frame];
+
+    current.iter().map(|(name, value)| {
+        let change = match (frame_knobs.get(frame + 1), frame.checked_sub(1)) {
+            (Some(next), _) => next.get(name).map_or(0.0, |next| next - value),
+            (None, Some(previous)) => frame_knobs[previous].get(name).map_or(0.0, |previous| value - previous),
+            (None, None) => 0.0,
+        };
+
+        (name.clone(), value + change * offset)
+    }).collect()
+}
+
 pub struct Animation {
     pub frames: Vec<Vec<u8>>,
     pub width: usize,
@@ -199,34 +671,28 @@ impl Animation {
         }
     }
 
-    pub fn add_frame(&mut self, rgb_buffer: &Vec<u8>) {
-        self.frames.push(rgb_buffer.clone());
+    pub fn add_frame(&mut self, rgba_buffer: &[u8]) {
+        self.frames.push(rgba_buffer.to_vec());
     }
 
-    pub fn save_as_file(&self, filename: String, delay: u32) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn save_as_file(&self, filename: String, fps: f32) -> Result<(), Box<dyn std::error::Error>> {
+        // gifs store the delay in hundredths of a second
+        // (rounded in those units directly, at least 1 since some viewers treat 0 as going as fast as they can)
+        let delay = (100.0 / fps).round().max(1.0) as u16;
+
         let file = File::create(&filename)?;
-        let mut encoder = GifEncoder::new(file);
-        encoder.set_repeat(Repeat::Infinite)?;
+        let mut encoder = gif::Encoder::new(file, self.width as u16, self.height as u16, &[])?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
 
         for frame_data in &self.frames {
-            let mut rgba = Vec::with_capacity(self.width * self.height * 4);
-
-            // convert to rgba because the Frame struct expects rgba
-            for chunk in frame_data.chunks(3) {
-                rgba.push(chunk[0]);
-                rgba.push(chunk[1]);
-                rgba.push(chunk[2]);
-                rgba.push(255);
-            }
-
-            let img: RgbaImage = ImageBuffer::from_raw(
-                self.width as u32,
-                self.height as u32,
-                rgba,
-            ).ok_or("Failed to create RGBA frame")?;
-
-            let frame = Frame::from_parts(img, 0, 0, Delay::from_numer_denom_ms(delay, 1));
-            encoder.encode_frame(frame)?;
+            let mut rgba = frame_data.clone();
+
+            // pixels with an alpha of 0 become the gif's transparent color, anything in between is just solid
+            let mut frame = gif::Frame::from_rgba_speed(self.width as u16, self.height as u16, &mut rgba, 1);
+            frame.delay = delay;
+            // clear each frame before the next one or the old frame shows through the transparent parts
+            frame.dispose = gif::DisposalMethod::Background;
+            encoder.write_frame(&frame)?;
         }
 
         println!("{} created.", filename);
@@ -0,0 +1,250 @@
+use std::error::Error;
+
+// a math expression written in a script like (sin(time * 2) * 10 + frame / 5)
+// it gets parsed once when the script is parsed and then evaluated every frame with whatever variables exist then
+#[derive(Clone, Debug)]
+pub enum Expression {
+    Number(f32),
+    Variable(String),
+    Negate(Box<Expression>),
+    Binary { operator: char, left: Box<Expression>, right: Box<Expression> },
+    Call { function: String, arguments: Vec<Expression> },
+}
+
+const FUNCTIONS: [(&str, usize); 17] = [
+    ("sin", 1), ("cos", 1), ("tan", 1), ("asin", 1), ("acos", 1), ("atan", 1),
+    ("abs", 1), ("sqrt", 1), ("floor", 1), ("ceil", 1), ("round", 1), ("exp", 1), ("ln", 1),
+    ("min", 2), ("max", 2), ("clamp", 3), ("noise", 1),
+];
+
+impl Expression {
+    pub fn parse(text: &str) -> Result<Expression, Box<dyn Error>> {
+        let tokens = tokenize(text)?;
+        let mut position = 0;
+
+        let expression = parse_sum(&tokens, &mut position)?;
+
+        if position != tokens.len() {
+            return Err(format!("Unexpected '{}' in expression: {}", tokens[position], text).into());
+        }
+
+        Ok(expression)
+    }
+
+    pub fn eval(&self, variable: &dyn Fn(&str) -> Option<f32>) -> Result<f32, Box<dyn Error>> {
+        match self {
+            Expression::Number(value) => Ok(*value),
+            Expression::Variable(name) => {
+                if name == "pi" {
+                    return Ok(std::f32::consts::PI);
+                }
+
+                variable(name).ok_or_else(|| format!("Unknown variable in expression: {}", name).into())
+            }
+            Expression::Negate(inner) => Ok(-inner.eval(variable)?),
+            Expression::Binary { operator, left, right } => {
+                let (a, b) = (left.eval(variable)?, right.eval(variable)?);
+
+                Ok(match operator {
+                    '+' => a + b,
+                    '-' => a - b,
+                    '*' => a * b,
+                    '/' => a / b,
+                    '%' => a.rem_euclid(b),
+                    _ => a.powf(b),
+                })
+            }
+            Expression::Call { function, arguments } => {
+                let values = arguments.iter().map(|argument| argument.eval(variable)).collect::<Result<Vec<f32>, _>>()?;
+
+                Ok(match (function.as_str(), values.as_slice()) {
+                    ("sin", [x]) => x.sin(),
+                    ("cos", [x]) => x.cos(),
+                    ("tan", [x]) => x.tan(),
+                    ("asin", [x]) => x.asin(),
+                    ("acos", [x]) => x.acos(),
+                    ("atan", [x]) => x.atan(),
+                    ("abs", [x]) => x.abs(),
+                    ("sqrt", [x]) => x.sqrt(),
+                    ("floor", [x]) => x.floor(),
+                    ("ceil", [x]) => x.ceil(),
+                    ("round", [x]) => x.round(),
+                    ("exp", [x]) => x.exp(),
+                    ("ln", [x]) => x.ln(),
+                    ("min", [a, b]) => a.min(*b),
+                    ("max", [a, b]) => a.max(*b),
+                    ("clamp", [x, low, high]) => x.max(*low).min(*high),
+                    ("noise", [x]) => noise(*x, 0),
+                    _ => return Err(format!("Wrong number of arguments for {} in expression.", function).into()),
+                })
+            }
+        }
+    }
+}
+
+pub fn noise(x: f32, seed: u32) -> f32 {
+    // smooth 1d noise between -1 and 1
+    // a few layers of value noise (random values at whole numbers with smooth curves in between) at doubling frequencies
+    let mut total = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    let mut max = 0.0;
+
+    for octave in 0..3 {
+        let x = x * frequency;
+        let i = x.floor();
+        let t = x - i;
+        let t = t * t * (3.0 - 2.0 * t);
+
+        let a = lattice(i as i32, seed.wrapping_add(octave));
+        let b = lattice(i as i32 + 1, seed.wrapping_add(octave));
+
+        total += (a + (b - a) * t) * amplitude;
+        max += amplitude;
+        amplitude /= 2.0;
+        frequency *= 2.0;
+    }
+
+    total / max
+}
+
+fn lattice(i: i32, seed: u32) -> f32 {
+    // hashes a whole number into a value between -1 and 1 that's always the same for the same seed
+    let mut h = (i as u32).wrapping_mul(0x9E3779B1) ^ seed.wrapping_mul(0x85EBCA77);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x2C1B3C6D);
+    h ^= h >> 12;
+    h = h.wrapping_mul(0x297A2D39);
+    h ^= h >> 15;
+
+    (h as f32 / u32::MAX as f32) * 2.0 - 1.0
+}
+
+fn tokenize(text: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut tokens: Vec<String> = vec![];
+    let chars: Vec<char> = text.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            tokens.push(chars[start..i].iter().collect());
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(chars[start..i].iter().collect());
+        } else if "+-*/%^(),".contains(c) {
+            tokens.push(c.to_string());
+            i += 1;
+        } else {
+            return Err(format!("Unexpected '{}' in expression: {}", c, text).into());
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn parse_sum(tokens: &[String], position: &mut usize) -> Result<Expression, Box<dyn Error>> {
+    let mut left = parse_product(tokens, position)?;
+
+    while let Some(operator) = tokens.get(*position).filter(|token| *token == "+" || *token == "-") {
+        let operator = operator.chars().next().unwrap();
+        *position += 1;
+        let right = parse_product(tokens, position)?;
+        left = Expression::Binary { operator, left: Box::new(left), right: Box::new(right) };
+    }
+
+    Ok(left)
+}
+
+fn parse_product(tokens: &[String], position: &mut usize) -> Result<Expression, Box<dyn Error>> {
+    let mut left = parse_unary(tokens, position)?;
+
+    while let Some(operator) = tokens.get(*position).filter(|token| *token == "*" || *token == "/" || *token == "%") {
+        let operator = operator.chars().next().unwrap();
+        *position += 1;
+        let right = parse_unary(tokens, position)?;
+        left = Expression::Binary { operator, left: Box::new(left), right: Box::new(right) };
+    }
+
+    Ok(left)
+}
+
+fn parse_unary(tokens: &[String], position: &mut usize) -> Result<Expression, Box<dyn Error>> {
+    if tokens.get(*position).is_some_and(|token| token == "-") {
+        *position += 1;
+        return Ok(Expression::Negate(Box::new(parse_unary(tokens, position)?)));
+    }
+
+    // ^ goes right to left so 2^3^2 is 2^(3^2)
+    let base = parse_atom(tokens, position)?;
+
+    if tokens.get(*position).is_some_and(|token| token == "^") {
+        *position += 1;
+        let exponent = parse_unary(tokens, position)?;
+        return Ok(Expression::Binary { operator: '^', left: Box::new(base), right: Box::new(exponent) });
+    }
+
+    Ok(base)
+}
+
+fn parse_atom(tokens: &[String], position: &mut usize) -> Result<Expression, Box<dyn Error>> {
+    let token = tokens.get(*position).ok_or("Expression ended early.")?;
+    *position += 1;
+
+    if token == "(" {
+        let inner = parse_sum(tokens, position)?;
+        expect(tokens, position, ")")?;
+        return Ok(inner);
+    }
+
+    if let Ok(value) = token.parse::<f32>() {
+        return Ok(Expression::Number(value));
+    }
+
+    if !token.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        return Err(format!("Unexpected '{}' in expression.", token).into());
+    }
+
+    if tokens.get(*position).is_some_and(|next| next == "(") {
+        let Some((_, count)) = FUNCTIONS.iter().find(|(name, _)| name == token) else {
+            return Err(format!("Unknown function in expression: {}", token).into());
+        };
+
+        *position += 1;
+        let mut arguments = vec![parse_sum(tokens, position)?];
+        while tokens.get(*position).is_some_and(|next| next == ",") {
+            *position += 1;
+            arguments.push(parse_sum(tokens, position)?);
+        }
+        expect(tokens, position, ")")?;
+
+        if arguments.len() != *count {
+            return Err(format!("{} takes {} arguments but received {}.", token, count, arguments.len()).into());
+        }
+
+        return Ok(Expression::Call { function: token.clone(), arguments });
+    }
+
+    Ok(Expression::Variable(token.clone()))
+}
+
+fn expect(tokens: &[String], position: &mut usize, expected: &str) -> Result<(), Box<dyn Error>> {
+    match tokens.get(*position) {
+        Some(token) if token == expected => {
+            *position += 1;
+            Ok(())
+        }
+        Some(token) => Err(format!("Expected '{}' in expression but received '{}'.", expected, token).into()),
+        None => Err(format!("Expected '{}' but the expression ended.", expected).into()),
+    }
+}
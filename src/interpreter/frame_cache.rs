@@ -0,0 +1,138 @@
+use std::{
+    collections::{HashMap, hash_map::DefaultHasher},
+    error::Error,
+    fs,
+    hash::{Hash, Hasher},
+    path::Path,
+};
+
+use super::parser::Command;
+
+// remembers what went into every frame saved in temp_frames so running the script again only renders the frames that changed
+// a frame is the same if the script (minus the commands that only pick frames or animate knobs), its knobs, and any mesh files are the same
+// changes to the engine itself aren't noticed so temp_frames should be cleared after changing the code
+pub struct FrameCache {
+    path: String,
+    script_hash: u64,
+    // the hash of every frame and the pass files that were saved with it
+    frames: HashMap<usize, (u64, Vec<String>)>,
+}
+
+impl FrameCache {
+    pub fn load(basename: &str, commands: &[Command]) -> Self {
+        let path = format!("temp_frames/{}.cache", basename);
+
+        // each line is a frame, its hash, and its pass files, anything that doesn't read properly just gets rendered again
+        let frames = fs::read_to_string(&path).map(|text| {
+            text.lines().filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                let frame = parts.next()?.parse().ok()?;
+                let hash = u64::from_str_radix(parts.next()?, 16).ok()?;
+                Some((frame, (hash, parts.map(String::from).collect())))
+            }).collect()
+        }).unwrap_or_default();
+
+        let mut hasher = DefaultHasher::new();
+        hash_commands(commands, &mut hasher);
+
+        Self { path, script_hash: hasher.finish(), frames }
+    }
+
+    pub fn frame_hash(&self, frame: usize, knobs: &[HashMap<String, f32>]) -> u64 {
+        // knobs is every knob list the frame uses (more than one with motion blur since it looks at the frames next to it)
+        let mut hasher = DefaultHasher::new();
+        self.script_hash.hash(&mut hasher);
+        // expressions can use the frame number directly
+        frame.hash(&mut hasher);
+
+        for knob_list in knobs {
+            let mut sorted: Vec<(&String, &f32)> = knob_list.iter().collect();
+            sorted.sort_by(|a, b| a.0.cmp(b.0));
+
+            for (name, value) in sorted {
+                name.hash(&mut hasher);
+                value.to_bits().hash(&mut hasher);
+            }
+        }
+
+        hasher.finish()
+    }
+
+    pub fn is_unchanged(&self, frame: usize, hash: u64, file_path: &str) -> bool {
+        // the pass files are checked too so deleting one of them renders the frame again
+        match self.frames.get(&frame) {
+            Some((cached_hash, pass_files)) => {
+                *cached_hash == hash && Path::new(file_path).exists() && pass_files.iter().all(|pass_file| Path::new(pass_file).exists())
+            }
+            None => false,
+        }
+    }
+
+    pub fn update(&mut self, frame: usize, hash: u64, pass_files: Vec<String>) -> Result<(), Box<dyn Error>> {
+        // saved after every frame so stopping halfway through still keeps the frames that were finished
+        self.frames.insert(frame, (hash, pass_files));
+
+        let mut frames: Vec<(&usize, &(u64, Vec<String>))> = self.frames.iter().collect();
+        frames.sort_by_key(|(frame, _)| **frame);
+
+        let text: String = frames.iter().map(|(frame, (hash, pass_files))| {
+            let mut line = format!("{} {:016x}", frame, hash);
+            for pass_file in pass_files {
+                line += &format!(" {}", pass_file);
+            }
+            line + "\n"
+        }).collect();
+        fs::write(&self.path, text)?;
+
+        Ok(())
+    }
+}
+
+fn hash_commands(commands: &[Command], hasher: &mut DefaultHasher) {
+    for command in commands {
+        match command {
+            // these only change which frames get rendered or what the knobs are, and the knobs are already in each frame's hash
+            // so changing a vary only renders the frames where its knob actually changed
+            Command::RenderFrames { .. } | Command::RenderEvery { .. } | Command::VaryKnob { .. } | Command::Tween { .. } |
+            Command::Keyframes { .. } | Command::Wiggle { .. } | Command::Oscillate { .. } | Command::LoopKnob { .. } |
+            Command::KnobExpression { .. } | Command::BlendKnobs { .. } | Command::Layer { .. } => continue,
+            Command::Mesh { file_path, .. } => {
+                let path = Path::new(file_path);
+                hash_file(path, path.parent().unwrap_or(Path::new(".")), hasher);
+            }
+            // same deal with luts, editing the .cube should re-render every frame that uses it
+            Command::Lut { file_path } => {
+                let path = Path::new(file_path);
+                hash_file(path, path.parent().unwrap_or(Path::new(".")), hasher);
+            }
+            Command::CreateComposite { commands, .. } => hash_commands(commands, hasher),
+            _ => {}
+        }
+
+        // commands don't implement Hash because of all the f32s but their debug text has everything in them
+        format!("{:?}", command).hash(hasher);
+    }
+}
+
+fn hash_file(path: &Path, directory: &Path, hasher: &mut DefaultHasher) {
+    // obj files can point to an mtl file which can point to textures, those are relative to the obj's folder
+    let Ok(bytes) = fs::read(path) else {
+        // the mesh or lut command will complain about it when the frame renders
+        "missing".hash(hasher);
+        return;
+    };
+
+    bytes.hash(hasher);
+
+    if !matches!(path.extension().and_then(|extension| extension.to_str()), Some("obj" | "mtl")) {
+        return;
+    }
+
+    for line in String::from_utf8_lossy(&bytes).lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+
+        if let ["mtllib" | "map_Kd", file, ..] = parts.as_slice() {
+            hash_file(&directory.join(file), directory, hasher);
+        }
+    }
+}
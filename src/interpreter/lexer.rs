@@ -10,9 +10,12 @@ pub fn tokenize(path: &str, keywords: HashMap<&str, TokenType>) -> Result<VecDeq
     let token_regex = Regex::new(r"(?x)
         (?P<Comment>//) |
         (?P<WhiteSpace> \s+) |
+        (?P<Time> -?(\d+\.?\d*|\.\d+)s\b) |
         (?P<Number> -?(\d+\.?\d*|\.\d+)) |
+        (?P<Easing> (cubicBezier|steps)\([^)]*\)) |
         (?P<FilePath>(?:\./|\../|[A-Za-z0-9_\-]+/)*[A-Za-z0-9_\-]+\.[A-Za-z0-9]+) |
         (?P<Identifier> [a-zA-Z_][a-zA-Z0-9_]*) |
+        (?P<Symbol> [-+*/%^(),]) |
         (?P<Unknown> \S)"
     ).unwrap();
 
@@ -28,12 +31,26 @@ pub fn tokenize(path: &str, keywords: HashMap<&str, TokenType>) -> Result<VecDeq
                 break;
             } else if captures.name("WhiteSpace").is_some() {
                 continue;
+            } else if let Some(time) = captures.name("Time") {
+                // seconds like 1.5s, the s gets dropped so the value can be parsed as a number
+                tokens.push_back(Token {
+                    value: time.as_str().trim_end_matches('s').to_string(),
+                    token_type: TokenType::Time,
+                    info: info.clone(),
+                });
             } else if let Some(number) = captures.name("Number") {
                 tokens.push_back(Token {
                     value: number.as_str().to_string(),
                     token_type: TokenType::Number,
                     info: info.clone(),
                 });
+            } else if let Some(easing) = captures.name("Easing") {
+                // cubicBezier(x1, y1, x2, y2) and steps(n) have their arguments checked by the parser
+                tokens.push_back(Token {
+                    value: easing.as_str().to_string(),
+                    token_type: TokenType::EasingFunction,
+                    info: info.clone(),
+                });
             } else if let Some(file_path) = captures.name("FilePath") {
                 tokens.push_back(Token {
                     value: file_path.as_str().to_string(),
@@ -50,6 +67,13 @@ pub fn tokenize(path: &str, keywords: HashMap<&str, TokenType>) -> Result<VecDeq
                     token_type,
                     info: info.clone(),
                 });
+            } else if let Some(symbol) = captures.name("Symbol") {
+                // only used inside expressions
+                tokens.push_back(Token {
+                    value: symbol.as_str().to_string(),
+                    token_type: TokenType::Symbol,
+                    info: info.clone(),
+                });
             } else if let Some(unknown) = captures.name("Unknown") {
                 return Err(format!("{} Token not recognized: {}", info, unknown.as_str()).into());
             }
@@ -1,15 +1,20 @@
 use super::read_lines;
 use image::ImageReader;
 use stl_io::read_stl;
-use crate::{render::{polygon_list::add_polygon, texture::MTL}};
+use crate::render::{indexed_mesh::IndexedMesh, polygon_list::add_polygon, texture::MTL};
 use std::{
     collections::HashMap, error::Error, fs::OpenOptions, path::{Path, PathBuf}
 };
 
+// position, texture coordinate, and normal indices of one face corner
+type ObjCorner = (usize, Option<usize>, Option<usize>);
+// which material every triangle uses (an index into the list) along with the materials themselves
+type MeshMaterials = (Vec<usize>, Vec<MTL>);
+
 pub fn handle_mesh(
-    polygons: &mut Vec<[f32; 4]>,
+    polygons: &mut IndexedMesh,
     file_path: &String,
-) -> Result<Option<(Vec<(String, [[f32; 2]; 3])>, HashMap<String, MTL>)>, Box<dyn Error>> {
+) -> Result<Option<MeshMaterials>, Box<dyn Error>> {
     let file = Path::new(file_path);
 
     if !file.exists() {
@@ -28,12 +33,22 @@ pub fn handle_mesh(
 
     if extension == "obj" {
         let mut vertices: Vec<[f32; 3]> = vec![];
+        let mut vertex_colors: Vec<Option<[f32; 3]>> = vec![];
         let mut vertex_textures: Vec<[f32; 2]> = vec![];
+        let mut vertex_normals: Vec<[f32; 3]> = vec![];
         let mut mtl_path: Option<String> = None;
-        let mut current_mtl: String = String::new(); 
-        let mut polygon_info: Vec<(String, [[f32; 2]; 3])> = vec![];
+        let mut current_mtl: String = String::new();
+        // kept for every triangle since mtllib doesn't have to come before the faces
+        let mut materials: Vec<String> = vec![];
+
+        // obj faces index positions, uvs, and normals separately
+        // so every unique v/vt/vn combination becomes one vertex in the indexed mesh
+        let mut corners: HashMap<ObjCorner, usize> = HashMap::new();
+        let mut all_uvs = true;
+        let mut all_normals = true;
+        let mut all_colors = true;
 
-        for line in read_lines(&file_path)?.map_while(Result::ok) {
+        for line in read_lines(file_path)?.map_while(Result::ok) {
             let line = line.trim();
             if line.starts_with("//") || line.starts_with("#") || line.is_empty() {
                 continue;
@@ -44,55 +59,59 @@ pub fn handle_mesh(
             match parts[0] {
                 "mtllib" => mtl_path = Some(parts[1].to_string()),
                 "usemtl" => current_mtl = parts[1].to_string(),
-                "v" => vertices.push([parts[1].parse()?, parts[2].parse()?, parts[3].parse()?]),
+                "v" => {
+                    vertices.push([parts[1].parse()?, parts[2].parse()?, parts[3].parse()?]);
+
+                    // some exporters tack an rgb color onto the end of the vertex
+                    if parts.len() >= 7 {
+                        vertex_colors.push(Some([parts[4].parse()?, parts[5].parse()?, parts[6].parse()?]));
+                    } else {
+                        vertex_colors.push(None);
+                    }
+                }
                 "vt" => vertex_textures.push([parts[1].parse()?, parts[2].parse()?]),
+                "vn" => vertex_normals.push([parts[1].parse()?, parts[2].parse()?, parts[3].parse()?]),
                 "f" => {
-                    let parse_v = |s: &str| s.split('/').next().unwrap().parse::<usize>().unwrap() - 1;
-
-                    let v0 = parse_v(parts[1]);
-                    let v1 = parse_v(parts[2]);
-                    let v2 = parse_v(parts[3]);
-
-                    add_polygon(
-                        polygons,
-                        vertices[v0][0], vertices[v0][1], vertices[v0][2],
-                        vertices[v1][0], vertices[v1][1], vertices[v1][2],
-                        vertices[v2][0], vertices[v2][1], vertices[v2][2],
-                    );
-
-                    let is_quad = parts.len() == 5;
-                    if is_quad {
-                        let v3 = parse_v(parts[4]);
-                        add_polygon(
-                            polygons,
-                            vertices[v0][0], vertices[v0][1], vertices[v0][2],
-                            vertices[v2][0], vertices[v2][1], vertices[v2][2],
-                            vertices[v3][0], vertices[v3][1], vertices[v3][2],
-                        );
+                    let mut face: Vec<usize> = vec![];
+
+                    for corner in &parts[1..] {
+                        let key = parse_corner(corner)?;
+
+                        let index = match corners.get(&key) {
+                            Some(index) => *index,
+                            None => {
+                                let (v, vt, vn) = key;
+                                let position = vertices.get(v).ok_or_else(|| format!("Mesh file '{}' references missing vertex {}", file_path, v + 1))?;
+                                let index = polygons.add_vertex(position[0], position[1], position[2]);
+                                polygons.position_ids.push(v);
+
+                                match vt.and_then(|vt| vertex_textures.get(vt)) {
+                                    Some(uv) => polygons.uvs.push(*uv),
+                                    None => { all_uvs = false; polygons.uvs.push([0.0, 0.0]); }
+                                }
+
+                                match vn.and_then(|vn| vertex_normals.get(vn)) {
+                                    Some(normal) => polygons.normals.push([normal[0], normal[1], normal[2], 0.0]),
+                                    None => { all_normals = false; polygons.normals.push([0.0, 0.0, 0.0, 0.0]); }
+                                }
+
+                                match vertex_colors[v] {
+                                    Some(color) => polygons.colors.push(color),
+                                    None => { all_colors = false; polygons.colors.push([1.0, 1.0, 1.0]); }
+                                }
+
+                                corners.insert(key, index);
+                                index
+                            }
+                        };
+
+                        face.push(index);
                     }
 
-                    // Only parse vt indices if MTL is being used
-                    if mtl_path.is_some() {
-                        let parse_vt = |s: &str| s.split('/').nth(1).unwrap().parse::<usize>().unwrap() - 1;
-
-                        let vt0 = parse_vt(parts[1]);
-                        let vt1 = parse_vt(parts[2]);
-                        let vt2 = parse_vt(parts[3]);
-
-                        polygon_info.push((current_mtl.clone(), [
-                            vertex_textures[vt0], 
-                            vertex_textures[vt1], 
-                            vertex_textures[vt2]
-                        ]));
-
-                        if is_quad {
-                            let vt3 = parse_vt(parts[4]);
-                            polygon_info.push((current_mtl.clone(), [
-                                vertex_textures[vt0], 
-                                vertex_textures[vt2], 
-                                vertex_textures[vt3]
-                            ]));
-                        }
+                    // split the face into a fan of triangles (quads become 0 1 2 and 0 2 3)
+                    for i in 1..face.len().saturating_sub(1) {
+                        polygons.add_triangle(face[0], face[i], face[i + 1]);
+                        materials.push(current_mtl.clone());
                     }
                 }
 
@@ -100,13 +119,33 @@ pub fn handle_mesh(
             }
         }
 
+        // attributes only count if every vertex has them, otherwise we fall back to computing them
+        if !all_uvs { polygons.uvs.clear(); }
+        if !all_normals { polygons.normals.clear(); }
+        if !all_colors { polygons.colors.clear(); }
+
         // if mtl is enabled render here otherwise allow the handle_mesh function that calls this
         if let Some(mtl_path) = mtl_path {
-            return Ok(Some((polygon_info, parse_mtl_from_obj(file, mtl_path.as_str())?)))
+            if !polygons.has_uvs() {
+                return Err(format!("Mesh file '{}' uses materials but doesn't have texture coordinates for every face", file_path).into());
+            }
+
+            // the names get looked up once here so rendering doesn't have to worry about missing ones
+            let (names, mtls): (Vec<String>, Vec<MTL>) = parse_mtl_from_obj(file, mtl_path.as_str())?.into_iter().unzip();
+            let materials = materials.iter().map(|material| {
+                if material.is_empty() {
+                    return Err(format!("Mesh file '{}' has faces before its first usemtl", file_path));
+                }
+
+                names.iter().position(|name| name == material)
+                    .ok_or_else(|| format!("Mesh file '{}' uses material '{}' which isn't in '{}'", file_path, material, mtl_path))
+            }).collect::<Result<Vec<usize>, String>>()?;
+
+            return Ok(Some((materials, mtls)))
         }
     } else {
         // i originally had this hand parsed using ascii along with the .obj, but i wanted more flexibility and binary stls are annoying to parse
-        let mut file = OpenOptions::new().read(true).open(file_path).unwrap();
+        let mut file = OpenOptions::new().read(true).open(file_path)?;
         let mesh = read_stl(&mut file)?;
 
         for polygon in mesh.into_triangle_vec() {
@@ -117,11 +156,34 @@ pub fn handle_mesh(
                 polygon.vertices[2][0], polygon.vertices[2][1], polygon.vertices[2][2],
             );
         }
+
+        // stl stores every triangle on its own so we have to merge the shared corners ourselves
+        polygons.weld();
     }
 
     Ok(None)
 }
 
+fn parse_corner(corner: &str) -> Result<ObjCorner, Box<dyn Error>> {
+    // v, v/vt, v//vn, or v/vt/vn (obj indices start at 1)
+    let mut indices = corner.split('/');
+    let parse_index = |s: Option<&str>| -> Result<Option<usize>, Box<dyn Error>> {
+        match s {
+            Some(s) if !s.is_empty() => s.parse::<usize>().ok()
+                .and_then(|i| i.checked_sub(1))
+                .map(Some)
+                .ok_or_else(|| format!("Invalid face index: {}", corner).into()),
+            _ => Ok(None),
+        }
+    };
+
+    let v = parse_index(indices.next())?.ok_or_else(|| format!("Invalid face index: {}", corner))?;
+    let vt = parse_index(indices.next())?;
+    let vn = parse_index(indices.next())?;
+
+    Ok((v, vt, vn))
+}
+
 pub fn parse_mtl_from_obj(obj_path: &Path, mtl_relative_path: &str) -> Result<HashMap<String, MTL>, Box<dyn Error>> {
     let obj_dir = obj_path.parent().unwrap_or_else(|| Path::new("."));
     let mtl_path = obj_dir.join(mtl_relative_path);
@@ -130,6 +192,7 @@ pub fn parse_mtl_from_obj(obj_path: &Path, mtl_relative_path: &str) -> Result<Ha
     let mut current_name = String::new();
     let mut current_ka = (0.0, 0.0, 0.0);
     let mut current_kd = (1.0, 1.0, 1.0);
+    let mut current_opacity = 1.0;
     let mut current_texture: Option<PathBuf> = None;
 
     for line in read_lines(&mtl_path)?.map_while(Result::ok) {
@@ -140,12 +203,13 @@ pub fn parse_mtl_from_obj(obj_path: &Path, mtl_relative_path: &str) -> Result<Ha
         match parts[0] {
             "newmtl" => {
                 if !current_name.is_empty() {
-                    let mtl = load_texture(&current_texture.unwrap(), current_ka, current_kd);
+                    let mtl = load_texture(&current_texture.unwrap(), current_ka, current_kd, current_opacity);
                     mtls.insert(current_name.clone(), mtl);
                 }
                 current_name = parts[1].to_string();
                 current_ka = (0.0, 0.0, 0.0);
                 current_kd = (1.0, 1.0, 1.0);
+                current_opacity = 1.0;
                 current_texture = None;
             }
             "Ka" => {
@@ -160,6 +224,13 @@ pub fn parse_mtl_from_obj(obj_path: &Path, mtl_relative_path: &str) -> Result<Ha
                 let b = parts[3].parse::<f32>()?;
                 current_kd = (r, g, b);
             }
+            // d is how solid it is and Tr is how see through it is, exporters use one or the other
+            "d" => {
+                current_opacity = parts[1].parse::<f32>()?.clamp(0.0, 1.0);
+            }
+            "Tr" => {
+                current_opacity = 1.0 - parts[1].parse::<f32>()?.clamp(0.0, 1.0);
+            }
             "map_Kd" => {
                 current_texture = Some(obj_dir.join(parts[1]));
             }
@@ -169,19 +240,20 @@ pub fn parse_mtl_from_obj(obj_path: &Path, mtl_relative_path: &str) -> Result<Ha
 
     // save the last mtl
     if !current_name.is_empty() {
-        let mtl = load_texture(&current_texture.unwrap(), current_ka, current_kd);
+        let mtl = load_texture(&current_texture.unwrap(), current_ka, current_kd, current_opacity);
         mtls.insert(current_name.clone(), mtl);
     }
 
     Ok(mtls)
 }
 
-fn load_texture(path: &Path, ka: (f32, f32, f32), kd: (f32, f32, f32)) -> MTL {
+fn load_texture(path: &Path, ka: (f32, f32, f32), kd: (f32, f32, f32), opacity: f32) -> MTL {
     let img = ImageReader::open(path).unwrap().decode().unwrap().to_rgb8();
     let (width, height) = img.dimensions();
     MTL {
         ka,
         kd,
+        opacity,
         data: img.into_vec(),
         width: width as usize,
         height: height as usize,
@@ -5,6 +5,8 @@ mod run_script;
 mod animation;
 mod coordinate_stack;
 mod mesh;
+mod expression;
+mod frame_cache;
 
 use std::{
     error::Error,
@@ -17,6 +19,7 @@ use std::{
 
 use parser::Parser;
 use run_script::{evaluate_commands, ScriptContext};
+pub use animation::FrameRange;
 use tokens::{TokenType, Function};
 
 static KEYWORDS: LazyLock<HashMap<&str, TokenType>> = LazyLock::new(|| {
@@ -26,6 +29,7 @@ static KEYWORDS: LazyLock<HashMap<&str, TokenType>> = LazyLock::new(|| {
     map.insert("save", TokenType::Command(Function::Save));
     map.insert("clear", TokenType::Command(Function::Clear));
     map.insert("camera", TokenType::Command(Function::SetCamera));
+    map.insert("tessellation", TokenType::Command(Function::SetTessellation));
 
     map.insert("push", TokenType::Command(Function::Push));
     map.insert("pop", TokenType::Command(Function::Pop));
@@ -44,6 +48,10 @@ static KEYWORDS: LazyLock<HashMap<&str, TokenType>> = LazyLock::new(|| {
     map.insert("circle", TokenType::Command(Function::Circle));
     map.insert("hermite", TokenType::Command(Function::Hermite));
     map.insert("bezier", TokenType::Command(Function::Bezier));
+    map.insert("hermite3d", TokenType::Command(Function::Hermite3D));
+    map.insert("bezier3d", TokenType::Command(Function::Bezier3D));
+    map.insert("catmull_rom", TokenType::Command(Function::CatmullRom));
+    map.insert("bspline", TokenType::Command(Function::BSpline));
 
     map.insert("polygon", TokenType::Command(Function::Polygon));
     map.insert("box", TokenType::Command(Function::Box));
@@ -51,6 +59,17 @@ static KEYWORDS: LazyLock<HashMap<&str, TokenType>> = LazyLock::new(|| {
     map.insert("torus", TokenType::Command(Function::Torus));
     map.insert("cylinder", TokenType::Command(Function::Cylinder));
     map.insert("cone", TokenType::Command(Function::Cone));
+    map.insert("ellipsoid", TokenType::Command(Function::Ellipsoid));
+    map.insert("capsule", TokenType::Command(Function::Capsule));
+    map.insert("frustum", TokenType::Command(Function::Frustum));
+    map.insert("plane", TokenType::Command(Function::Plane));
+    map.insert("disc", TokenType::Command(Function::Disc));
+    map.insert("annulus", TokenType::Command(Function::Annulus));
+    map.insert("prism", TokenType::Command(Function::Prism));
+    map.insert("pyramid", TokenType::Command(Function::Pyramid));
+    map.insert("icosphere", TokenType::Command(Function::Icosphere));
+    map.insert("lathe", TokenType::Command(Function::Lathe));
+    map.insert("extrude", TokenType::Command(Function::Extrude));
     map.insert("mesh", TokenType::Command(Function::Mesh));
 
     map.insert("light", TokenType::Command(Function::AddLight));
@@ -58,37 +77,107 @@ static KEYWORDS: LazyLock<HashMap<&str, TokenType>> = LazyLock::new(|| {
     map.insert("ambient", TokenType::Command(Function::SetAmbient));
     map.insert("constants", TokenType::Command(Function::DefineConstants));
     map.insert("shading", TokenType::Command(Function::SetShading));
+    map.insert("png_compression", TokenType::Command(Function::SetPngCompression));
+    map.insert("tone_mapping", TokenType::Command(Function::SetToneMapping));
+    map.insert("exposure", TokenType::Command(Function::SetExposure));
+    map.insert("gamma", TokenType::Command(Function::SetGamma));
+    map.insert("background", TokenType::Command(Function::SetBackground));
+    map.insert("passes", TokenType::Command(Function::SetPasses));
+    map.insert("outline", TokenType::Command(Function::SetOutline));
+    map.insert("outline_creases", TokenType::Command(Function::SetOutlineCreases));
+    map.insert("cull", TokenType::Command(Function::SetCulling));
+    map.insert("two_sided", TokenType::Command(Function::SetTwoSidedLighting));
+    map.insert("z_test", TokenType::Command(Function::SetDepthTest));
+    map.insert("z_write", TokenType::Command(Function::SetDepthWrite));
+    map.insert("z_compare", TokenType::Command(Function::SetDepthCompare));
+    map.insert("crease", TokenType::Command(Function::SetCreaseAngle));
 
     map.insert("basename", TokenType::Command(Function::SetBaseName));
     map.insert("set", TokenType::Command(Function::SetKnob));
     map.insert("save_knobs", TokenType::Command(Function::SaveKnobList));
+    map.insert("blend_knobs", TokenType::Command(Function::BlendKnobs));
+    map.insert("layer", TokenType::Command(Function::Layer));
     map.insert("tween", TokenType::Command(Function::Tween));
     map.insert("frames", TokenType::Command(Function::SetFrames));
+    map.insert("fps", TokenType::Command(Function::SetFrameRate));
+    map.insert("duration", TokenType::Command(Function::SetDuration));
+    map.insert("motion_blur", TokenType::Command(Function::SetMotionBlur));
+    map.insert("render_frames", TokenType::Command(Function::RenderFrames));
+    map.insert("render_frame", TokenType::Command(Function::RenderFrame));
+    map.insert("render_every", TokenType::Command(Function::RenderEvery));
     map.insert("vary", TokenType::Command(Function::VaryKnob));
+    map.insert("keyframes", TokenType::Command(Function::Keyframes));
+    map.insert("wiggle", TokenType::Command(Function::Wiggle));
+    map.insert("oscillate", TokenType::Command(Function::Oscillate));
+    map.insert("loop", TokenType::Command(Function::LoopKnob));
+    map.insert("pingpong", TokenType::Command(Function::PingPongKnob));
+    map.insert("expression", TokenType::Command(Function::KnobExpression));
     map.insert("setknobs", TokenType::Command(Function::SetAllKnobs));
-
+    map.insert("path", TokenType::Command(Function::DefinePath));
+    map.insert("follow", TokenType::Command(Function::FollowPath));
+    map.insert("joint", TokenType::Command(Function::DefineJoint));
+    map.insert("pose", TokenType::Command(Function::PoseJoint));
+
+    map.insert("ease", TokenType::EasingFunction);
+    map.insert("easeIn", TokenType::EasingFunction);
+    map.insert("easeOut", TokenType::EasingFunction);
+    map.insert("easeInOut", TokenType::EasingFunction);
+    map.insert("easeInSine", TokenType::EasingFunction);
+    map.insert("easeOutSine", TokenType::EasingFunction);
+    map.insert("easeInOutSine", TokenType::EasingFunction);
+    map.insert("easeInQuad", TokenType::EasingFunction);
+    map.insert("easeOutQuad", TokenType::EasingFunction);
+    map.insert("easeInOutQuad", TokenType::EasingFunction);
     map.insert("easeInCubic", TokenType::EasingFunction);
     map.insert("easeOutCubic", TokenType::EasingFunction);
+    map.insert("easeInOutCubic", TokenType::EasingFunction);
+    map.insert("easeInQuart", TokenType::EasingFunction);
+    map.insert("easeOutQuart", TokenType::EasingFunction);
+    map.insert("easeInOutQuart", TokenType::EasingFunction);
+    map.insert("easeInQuint", TokenType::EasingFunction);
+    map.insert("easeOutQuint", TokenType::EasingFunction);
+    map.insert("easeInOutQuint", TokenType::EasingFunction);
     map.insert("easeInExpo", TokenType::EasingFunction);
     map.insert("easeOutExpo", TokenType::EasingFunction);
+    map.insert("easeInOutExpo", TokenType::EasingFunction);
+    map.insert("easeInCirc", TokenType::EasingFunction);
+    map.insert("easeOutCirc", TokenType::EasingFunction);
+    map.insert("easeInOutCirc", TokenType::EasingFunction);
+    map.insert("easeInBack", TokenType::EasingFunction);
+    map.insert("easeOutBack", TokenType::EasingFunction);
+    map.insert("easeInOutBack", TokenType::EasingFunction);
+    map.insert("easeInElastic", TokenType::EasingFunction);
+    map.insert("easeOutElastic", TokenType::EasingFunction);
+    map.insert("easeInOutElastic", TokenType::EasingFunction);
+    map.insert("easeInBounce", TokenType::EasingFunction);
+    map.insert("easeOutBounce", TokenType::EasingFunction);
+    map.insert("easeInOutBounce", TokenType::EasingFunction);
 
     map.insert("save_coord_system", TokenType::Command(Function::SaveCoordSystem));
 
     map.insert("composite", TokenType::Command(Function::CreateComposite));
     map.insert("run_composite", TokenType::Command(Function::RunComposite));
 
+    map.insert("blur", TokenType::Command(Function::Blur));
+    map.insert("bloom", TokenType::Command(Function::Bloom));
+    map.insert("vignette", TokenType::Command(Function::Vignette));
+    map.insert("grade", TokenType::Command(Function::Grade));
+    map.insert("lut", TokenType::Command(Function::Lut));
+    map.insert("depth_of_field", TokenType::Command(Function::DepthOfField));
+    map.insert("edges", TokenType::Command(Function::EdgeDetect));
+
     map.insert("generate_rayfiles", TokenType::Command(Function::GenerateRayFiles));
     map.insert("focal", TokenType::Command(Function::SetFocalLength));
 
     map
 });
 
-pub fn run_script(path: &str) -> Result<(), Box<dyn Error>> {
+pub fn run_script(path: &str, frame_range: FrameRange) -> Result<(), Box<dyn Error>> {
     let tokens = lexer::tokenize(path, KEYWORDS.clone())?;
 
     let commands = Parser::new().generate_command_list(tokens)?;
 
-    evaluate_commands(&mut ScriptContext::new(), commands)?;
+    evaluate_commands(&mut ScriptContext::new(path), commands, frame_range)?;
 
     Ok(())
 }
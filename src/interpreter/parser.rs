@@ -5,16 +5,21 @@ use std::{
 };
 
 use crate::{
-    constants::ShadingMode,
+    constants::{CullMode, ShadingMode, Tessellation, DEFAULT_TOON_BANDS, DEFAULT_ADAPTIVE_SEGMENT_LENGTH, DEFAULT_ICOSPHERE_SUBDIVISIONS, MAX_ICOSPHERE_SUBDIVISIONS, MIN_PARAMETRIC_STEPS, MAX_PARAMETRIC_STEPS},
     matrix::Rotation,
+    render::{DepthCompare, Pass, ToneMapping},
+};
+use super::{
+    expression::Expression,
+    tokens::{Token, TokenType, Function},
 };
-use super::tokens::{Token, TokenType, Function};
 
 #[derive(Clone, Debug)]
 pub enum Command {
     Display,
     Save { file_path: String },
-    SetCamera { eye_x: f32, eye_y: f32, eye_z: f32, aim_x: f32, aim_y: f32, aim_z: f32 },
+    SetCamera { eye_x: Parameter, eye_y: Parameter, eye_z: Parameter, aim_x: Parameter, aim_y: Parameter, aim_z: Parameter },
+    SetTessellation { tessellation: Tessellation },
     CreateComposite { name: String, commands: Vec<Command> },
     RunComposite { name: String },
 
@@ -24,39 +29,172 @@ pub enum Command {
     Move { a: f32, b: f32, c: f32, knob: Option<String> },
     Scale { a: f32, b: f32, c: f32, knob: Option<String> },
     Rotate { axis: Rotation, degrees: f32, knob: Option<String> },
-    SaveCoordSystem { name: String },
+    SaveCoordSystem { name: String, joint: Option<String> },
+    DefineJoint { name: String, parent: Option<String>, x: f32, y: f32, z: f32 },
+    PoseJoint { name: String, axis: Rotation, degrees: f32, knob: Option<String> },
+    FollowPath { name: String, t: f32, knob: Option<String> },
 
     Line {  x0: f32, y0: f32, z0: f32, x1: f32, y1: f32, z1: f32 },
-    Circle { x: f32, y: f32, z: f32, r: f32 },
-    Hermite { x0: f32, y0: f32, x1: f32, y1: f32, rx0: f32, ry0: f32, rx1: f32, ry1: f32 },
-    Bezier { x0: f32, y0: f32, x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32 },
+    Circle { x: f32, y: f32, z: f32, r: f32, steps: Option<i32> },
+    Hermite { x0: f32, y0: f32, x1: f32, y1: f32, rx0: f32, ry0: f32, rx1: f32, ry1: f32, steps: Option<i32> },
+    Bezier { x0: f32, y0: f32, x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32, steps: Option<i32> },
+    Hermite3D { x0: f32, y0: f32, z0: f32, x1: f32, y1: f32, z1: f32, rx0: f32, ry0: f32, rz0: f32, rx1: f32, ry1: f32, rz1: f32, steps: Option<i32> },
+    Bezier3D { x0: f32, y0: f32, z0: f32, x1: f32, y1: f32, z1: f32, x2: f32, y2: f32, z2: f32, x3: f32, y3: f32, z3: f32, steps: Option<i32> },
+    CatmullRom { points: Vec<[f32; 3]>, steps: Option<i32> },
+    BSpline { points: Vec<[f32; 3]>, steps: Option<i32> },
 
     Polygon { constants: Option<String>, x0: f32, y0: f32, z0: f32, x1: f32, y1: f32, z1: f32, x2: f32, y2: f32, z2: f32, coord_system: Option<String> },
     Box { constants: Option<String>, x: f32, y: f32, z: f32, w: f32, h: f32, d: f32, coord_system: Option<String> },
-    Sphere { constants: Option<String>, x: f32, y: f32, z: f32, r: f32, coord_system: Option<String> },
-    Torus { constants: Option<String>, x: f32, y: f32, z: f32, r0: f32, r1: f32, coord_system: Option<String> },
-    Cylinder { constants: Option<String>, x: f32, y: f32, z: f32, r: f32, h: f32, coord_system: Option<String> },
-    Cone { constants: Option<String>, x: f32, y: f32, z: f32, r: f32, h: f32, coord_system: Option<String> },
+    Sphere { constants: Option<String>, x: f32, y: f32, z: f32, r: f32, steps: Option<i32>, coord_system: Option<String> },
+    Torus { constants: Option<String>, x: f32, y: f32, z: f32, r0: f32, r1: f32, steps: Option<i32>, coord_system: Option<String> },
+    Cylinder { constants: Option<String>, x: f32, y: f32, z: f32, r: f32, h: f32, steps: Option<i32>, coord_system: Option<String> },
+    Cone { constants: Option<String>, x: f32, y: f32, z: f32, r: f32, h: f32, steps: Option<i32>, coord_system: Option<String> },
+    Ellipsoid { constants: Option<String>, x: f32, y: f32, z: f32, rx: f32, ry: f32, rz: f32, steps: Option<i32>, coord_system: Option<String> },
+    Capsule { constants: Option<String>, x: f32, y: f32, z: f32, r: f32, h: f32, steps: Option<i32>, coord_system: Option<String> },
+    Frustum { constants: Option<String>, x: f32, y: f32, z: f32, r0: f32, r1: f32, h: f32, steps: Option<i32>, coord_system: Option<String> },
+    Plane { constants: Option<String>, x: f32, y: f32, z: f32, w: f32, d: f32, steps: Option<i32>, coord_system: Option<String> },
+    Disc { constants: Option<String>, x: f32, y: f32, z: f32, r: f32, steps: Option<i32>, coord_system: Option<String> },
+    Annulus { constants: Option<String>, x: f32, y: f32, z: f32, r0: f32, r1: f32, steps: Option<i32>, coord_system: Option<String> },
+    Prism { constants: Option<String>, x: f32, y: f32, z: f32, r: f32, h: f32, sides: i32, coord_system: Option<String> },
+    Pyramid { constants: Option<String>, x: f32, y: f32, z: f32, r: f32, h: f32, sides: i32, coord_system: Option<String> },
+    Icosphere { constants: Option<String>, x: f32, y: f32, z: f32, r: f32, subdivisions: usize, coord_system: Option<String> },
+    Lathe { constants: Option<String>, axis: Rotation, curve: Curve, steps: Option<i32>, coord_system: Option<String> },
+    Extrude { constants: Option<String>, path: ExtrudePath, steps: Option<i32>, outline: Vec<Command>, coord_system: Option<String> },
     Mesh { constants: Option<String>, file_path: String, coord_system: Option<String> },
 
     ClearLights,
-    AddLight { r: f32, g: f32, b: f32, x: f32, y: f32, z: f32 },
-    SetAmbient { r: f32, g: f32, b: f32 },
-    DefineConstants { name: String, kar: f32, kdr: f32, ksr: f32, kag: f32, kdg: f32, ksg: f32, kab: f32, kdb: f32, ksb: f32 },
+    AddLight { r: Parameter, g: Parameter, b: Parameter, x: Parameter, y: Parameter, z: Parameter },
+    SetAmbient { r: Parameter, g: Parameter, b: Parameter },
+    DefineConstants { name: String, kar: Parameter, kdr: Parameter, ksr: Parameter, kag: Parameter, kdg: Parameter, ksg: Parameter, kab: Parameter, kdb: Parameter, ksb: Parameter, opacity: Option<Box<Parameter>> }, // boxed so it doesn't make every command bigger
     SetShading { shading_mode: ShadingMode },
+    SetPngCompression { compression: png::Compression },
+    SetToneMapping { tone_mapping: ToneMapping },
+    SetExposure { stops: Parameter },
+    SetGamma { srgb: bool },
+    SetBackground { color: Option<(Parameter, Parameter, Parameter)> }, // None is transparent
+    SetPasses { passes: Vec<Pass> },
+    SetOutline { width: Option<Parameter>, color: Option<(Parameter, Parameter, Parameter)> }, // no width turns it off
+    SetOutlineCreases { degrees: Option<f32> },
+    SetCulling { mode: CullMode },
+    SetTwoSidedLighting { enabled: bool },
+    SetDepthTest { enabled: bool },
+    SetDepthWrite { enabled: bool },
+    SetDepthCompare { compare: DepthCompare },
+
+    Blur { radius: Parameter },
+    Bloom { threshold: Parameter, strength: Parameter, radius: Parameter },
+    Vignette { strength: Parameter },
+    Grade { brightness: Parameter, contrast: Parameter, saturation: Parameter },
+    Lut { file_path: String },
+    DepthOfField { focus: Parameter, range: Parameter, radius: Parameter },
+    EdgeDetect { threshold: Parameter, color: Option<(Parameter, Parameter, Parameter)> },
+    SetCreaseAngle { degrees: f32 },
 
     SetBaseName { name: String },
     SetKnob { name: String, value: f32 },
-    SaveKnobList { name: String },
-    Tween { start_frame: usize, end_frame: usize, knoblist0: String, knoblist1: String, easing: Option<String> },
+    SaveKnobList { name: String, frame: Option<Time> },
+    BlendKnobs { name: String, lists: Vec<(String, f32)> },
+    Layer { mode: LayerMode, mask: Vec<String>, commands: Vec<Command> },
+    Tween { start_frame: Time, end_frame: Time, knoblist0: String, knoblist1: String, easing: Option<Easing> },
     SetFrames { num_frames: usize },
-    VaryKnob { knob: String, start_frame: usize, end_frame: usize, start_val: f32, end_val: f32, easing: Option<String> },
+    SetFrameRate { fps: f32 },
+    SetDuration { seconds: f32 },
+    SetMotionBlur { samples: usize, shutter_angle: f32 },
+    RenderFrames { start: Time, end: Time },
+    RenderEvery { every: usize },
+    VaryKnob { knob: String, start_frame: Time, end_frame: Time, start_val: f32, end_val: f32, easing: Option<Easing> },
+    Keyframes { knob: String, keys: Vec<Keyframe> },
+    Wiggle { knob: String, frequency: f32, amplitude: f32, seed: u32 },
+    Oscillate { knob: String, wave: Wave, period: Time, amplitude: f32, phase: f32 },
+    LoopKnob { knob: String, pingpong: bool },
+    KnobExpression { knob: String, expression: Expression },
     SetAllKnobs { value: f32 },
+    DefinePath { name: String, curve: Box<Command> },
 
     GenerateRayFiles,
     SetFocalLength { length: f32 },
 }
 
+// a 2d curve given inline as the argument of another command (lathe and extrude)
+#[derive(Clone, Debug)]
+pub enum Curve {
+    Hermite { x0: f32, y0: f32, x1: f32, y1: f32, rx0: f32, ry0: f32, rx1: f32, ry1: f32 },
+    Bezier { x0: f32, y0: f32, x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32 },
+}
+
+#[derive(Clone, Debug)]
+pub enum ExtrudePath {
+    Depth(f32),
+    Curve(Curve),
+}
+
+#[derive(Clone, Debug)]
+pub enum Easing {
+    Named(String),
+    CubicBezier { x1: f32, y1: f32, x2: f32, y2: f32 },
+    Steps(usize),
+}
+
+// how a keyframe gets to the next one
+#[derive(Clone, Debug)]
+pub enum Interpolation {
+    Step,
+    Linear,
+    CatmullRom,
+    Bezier { x1: f32, y1: f32, x2: f32, y2: f32 },
+}
+
+// how a layer's knobs get combined with the knobs from before it
+#[derive(Clone, Debug)]
+pub enum LayerMode {
+    Add,
+    Blend(Parameter),
+}
+
+// a number that can also come from a knob or an expression so it can change every frame
+#[derive(Clone, Debug)]
+pub enum Parameter {
+    Number(f32),
+    Knob(String),
+    Expression(Expression),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Wave {
+    Sine,
+    Triangle,
+    Square,
+    Saw,
+}
+
+// animation commands take either a frame number or seconds (1.5s)
+#[derive(Clone, Copy, Debug)]
+pub enum Time {
+    Frame(usize),
+    Seconds(f32),
+}
+
+impl Time {
+    pub fn to_frame(self, fps: f32) -> usize {
+        self.to_frames_exact(fps).round() as usize
+    }
+
+    pub fn to_frames_exact(self, fps: f32) -> f32 {
+        // without rounding for things like periods that don't have to land on a frame
+        match self {
+            Time::Frame(frame) => frame as f32,
+            Time::Seconds(seconds) => seconds * fps,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Keyframe {
+    pub frame: Time,
+    pub value: f32,
+    pub interpolation: Interpolation,
+}
+
 pub struct Parser {
     stack: VecDeque<Token>,
 }
@@ -95,6 +233,16 @@ impl Parser {
         }
     }
 
+    fn pop_switch(&mut self) -> Result<bool, Box<dyn Error>> {
+        // for settings that are either on or off
+        let switch_str = self.pop_expected(TokenType::Identifier)?.value.to_lowercase();
+        match switch_str.as_str() {
+            "on" => Ok(true),
+            "off" => Ok(false),
+            _ => Err(format!("Expected on or off but received {}.", switch_str).into()),
+        }
+    }
+
     pub fn generate_command_list(&mut self, tokens: VecDeque<Token>) -> Result<Vec<Command>, Box<dyn Error>> {
         let mut commands: Vec<Command> = vec![];
 
@@ -126,27 +274,83 @@ impl Parser {
                     Function::Circle => { self.handle_circle() }
                     Function::Hermite => { self.handle_hermite() }
                     Function::Bezier => { self.handle_bezier() }
+                    Function::Hermite3D => { self.handle_hermite3d() }
+                    Function::Bezier3D => { self.handle_bezier3d() }
+                    Function::CatmullRom => { self.handle_catmull_rom() }
+                    Function::BSpline => { self.handle_b_spline() }
                     Function::Polygon => { self.handle_polygon() }
                     Function::Box => { self.handle_box() }
                     Function::Sphere => { self.handle_sphere() }
                     Function::Torus => { self.handle_torus() }
                     Function::Cylinder => { self.handle_cylinder() }
                     Function::Cone => { self.handle_cone() }
+                    Function::Ellipsoid => { self.handle_ellipsoid() }
+                    Function::Capsule => { self.handle_capsule() }
+                    Function::Frustum => { self.handle_frustum() }
+                    Function::Plane => { self.handle_plane() }
+                    Function::Disc => { self.handle_disc() }
+                    Function::Annulus => { self.handle_annulus() }
+                    Function::Prism => { self.handle_prism() }
+                    Function::Pyramid => { self.handle_pyramid() }
+                    Function::Icosphere => { self.handle_icosphere() }
+                    Function::Lathe => { self.handle_lathe() }
+                    Function::Extrude => { self.handle_extrude() }
                     Function::Mesh => { self.handle_mesh() }
                     Function::ClearLights => { Ok(Command::ClearLights) }
                     Function::AddLight => { self.handle_add_light() }
                     Function::SetAmbient => { self.handle_set_ambient() }
                     Function::DefineConstants => { self.handle_define_constants() }
                     Function::SetShading => { self.handle_set_shading() }
+                    Function::SetPngCompression => { self.handle_set_png_compression() }
+                    Function::SetToneMapping => { self.handle_set_tone_mapping() }
+                    Function::SetExposure => { self.handle_set_exposure() }
+                    Function::SetGamma => { self.handle_set_gamma() }
+                    Function::SetBackground => { self.handle_set_background() }
+                    Function::SetPasses => { self.handle_set_passes() }
+                    Function::SetOutline => { self.handle_set_outline() }
+                    Function::SetOutlineCreases => { self.handle_set_outline_creases() }
+                    Function::SetCulling => { self.handle_set_culling() }
+                    Function::SetTwoSidedLighting => { Ok(Command::SetTwoSidedLighting { enabled: self.pop_switch()? }) }
+                    Function::SetDepthTest => { Ok(Command::SetDepthTest { enabled: self.pop_switch()? }) }
+                    Function::SetDepthWrite => { Ok(Command::SetDepthWrite { enabled: self.pop_switch()? }) }
+                    Function::SetDepthCompare => { self.handle_set_depth_compare() }
+
+                    Function::Blur => { self.handle_blur() }
+                    Function::Bloom => { self.handle_bloom() }
+                    Function::Vignette => { self.handle_vignette() }
+                    Function::Grade => { self.handle_grade() }
+                    Function::Lut => { self.handle_lut() }
+                    Function::DepthOfField => { self.handle_depth_of_field() }
+                    Function::EdgeDetect => { self.handle_edge_detect() }
+                    Function::SetCreaseAngle => { self.handle_set_crease_angle() }
                     Function::SetCamera => { self.handle_set_camera() }
+                    Function::SetTessellation => { self.handle_set_tessellation() }
                     Function::SetBaseName => { self.handle_set_base_name() }
                     Function::SetKnob => { self.handle_set_knob() }
                     Function::SaveKnobList => { self.handle_save_knob_list() }
+                    Function::BlendKnobs => { self.handle_blend_knobs() }
+                    Function::Layer => { self.handle_layer() }
                     Function::Tween => { self.handle_tween() }
                     Function::SetFrames => { self.handle_set_frames() }
+                    Function::SetFrameRate => { self.handle_set_frame_rate() }
+                    Function::SetDuration => { self.handle_set_duration() }
+                    Function::SetMotionBlur => { self.handle_set_motion_blur() }
+                    Function::RenderFrames => { self.handle_render_frames() }
+                    Function::RenderFrame => { self.handle_render_frame() }
+                    Function::RenderEvery => { self.handle_render_every() }
                     Function::VaryKnob => { self.handle_vary_knob() }
+                    Function::Keyframes => { self.handle_keyframes() }
+                    Function::Wiggle => { self.handle_wiggle() }
+                    Function::Oscillate => { self.handle_oscillate() }
+                    Function::LoopKnob => { self.handle_loop_knob(false) }
+                    Function::PingPongKnob => { self.handle_loop_knob(true) }
+                    Function::KnobExpression => { self.handle_knob_expression() }
                     Function::SetAllKnobs => { self.handle_set_all_knobs() }
                     Function::SaveCoordSystem => { self.handle_save_coord_system() }
+                    Function::DefineJoint => { self.handle_define_joint() }
+                    Function::PoseJoint => { self.handle_pose_joint() }
+                    Function::FollowPath => { self.handle_follow_path() }
+                    Function::DefinePath => { self.handle_define_path() }
                     Function::GenerateRayFiles => { Ok(Command::GenerateRayFiles) }
                     Function::SetFocalLength => { self.handle_set_focal_length() }
                     Function::CreateComposite => { self.handle_create_composite() }
@@ -215,8 +419,9 @@ impl Parser {
         let y = Parser::convert_to_f32(self.pop()?.value)?;
         let z = Parser::convert_to_f32(self.pop()?.value)?;
         let r = Parser::convert_to_f32(self.pop()?.value)?;
+        let steps = self.pop_optional_steps()?;
 
-        Ok(Command::Circle { x, y, z, r })
+        Ok(Command::Circle { x, y, z, r, steps })
     }
 
     fn handle_hermite(&mut self) -> Result<Command, Box<dyn Error>> {
@@ -228,8 +433,9 @@ impl Parser {
         let ry0 = Parser::convert_to_f32(self.pop()?.value)?;
         let rx1 = Parser::convert_to_f32(self.pop()?.value)?;
         let ry1 = Parser::convert_to_f32(self.pop()?.value)?;
+        let steps = self.pop_optional_steps()?;
 
-        Ok(Command::Hermite { x0, y0, x1, y1, rx0, ry0, rx1, ry1 })
+        Ok(Command::Hermite { x0, y0, x1, y1, rx0, ry0, rx1, ry1, steps })
     }
 
     fn handle_bezier(&mut self) -> Result<Command, Box<dyn Error>> {
@@ -241,8 +447,85 @@ impl Parser {
         let y2 = Parser::convert_to_f32(self.pop()?.value)?;
         let x3 = Parser::convert_to_f32(self.pop()?.value)?;
         let y3 = Parser::convert_to_f32(self.pop()?.value)?;
+        let steps = self.pop_optional_steps()?;
+
+        Ok(Command::Bezier { x0, y0, x1, y1, x2, y2, x3, y3, steps })
+    }
+
+    fn handle_hermite3d(&mut self) -> Result<Command, Box<dyn Error>> {
+        let x0 = Parser::convert_to_f32(self.pop()?.value)?;
+        let y0 = Parser::convert_to_f32(self.pop()?.value)?;
+        let z0 = Parser::convert_to_f32(self.pop()?.value)?;
+        let x1 = Parser::convert_to_f32(self.pop()?.value)?;
+        let y1 = Parser::convert_to_f32(self.pop()?.value)?;
+        let z1 = Parser::convert_to_f32(self.pop()?.value)?;
+        let rx0 = Parser::convert_to_f32(self.pop()?.value)?;
+        let ry0 = Parser::convert_to_f32(self.pop()?.value)?;
+        let rz0 = Parser::convert_to_f32(self.pop()?.value)?;
+        let rx1 = Parser::convert_to_f32(self.pop()?.value)?;
+        let ry1 = Parser::convert_to_f32(self.pop()?.value)?;
+        let rz1 = Parser::convert_to_f32(self.pop()?.value)?;
+        let steps = self.pop_optional_steps()?;
+
+        Ok(Command::Hermite3D { x0, y0, z0, x1, y1, z1, rx0, ry0, rz0, rx1, ry1, rz1, steps })
+    }
+
+    fn handle_bezier3d(&mut self) -> Result<Command, Box<dyn Error>> {
+        let x0 = Parser::convert_to_f32(self.pop()?.value)?;
+        let y0 = Parser::convert_to_f32(self.pop()?.value)?;
+        let z0 = Parser::convert_to_f32(self.pop()?.value)?;
+        let x1 = Parser::convert_to_f32(self.pop()?.value)?;
+        let y1 = Parser::convert_to_f32(self.pop()?.value)?;
+        let z1 = Parser::convert_to_f32(self.pop()?.value)?;
+        let x2 = Parser::convert_to_f32(self.pop()?.value)?;
+        let y2 = Parser::convert_to_f32(self.pop()?.value)?;
+        let z2 = Parser::convert_to_f32(self.pop()?.value)?;
+        let x3 = Parser::convert_to_f32(self.pop()?.value)?;
+        let y3 = Parser::convert_to_f32(self.pop()?.value)?;
+        let z3 = Parser::convert_to_f32(self.pop()?.value)?;
+        let steps = self.pop_optional_steps()?;
+
+        Ok(Command::Bezier3D { x0, y0, z0, x1, y1, z1, x2, y2, z2, x3, y3, z3, steps })
+    }
+
+    fn handle_catmull_rom(&mut self) -> Result<Command, Box<dyn Error>> {
+        let steps = self.pop_optional_steps()?;
+        let points = self.handle_point_block("catmull_rom")?;
 
-        Ok(Command::Bezier { x0, y0, x1, y1, x2, y2, x3, y3 })
+        Ok(Command::CatmullRom { points, steps })
+    }
+
+    fn handle_b_spline(&mut self) -> Result<Command, Box<dyn Error>> {
+        let steps = self.pop_optional_steps()?;
+        let points = self.handle_point_block("bspline")?;
+
+        Ok(Command::BSpline { points, steps })
+    }
+
+    fn handle_point_block(&mut self, name: &str) -> Result<Vec<[f32; 3]>, Box<dyn Error>> {
+        // begin x0 y0 z0 x1 y1 z1 ... end
+        let _ = self.pop_expected(TokenType::Begin)?;
+        let mut values: Vec<f32> = vec![];
+
+        loop {
+            let token = self.pop().map_err(|_| format!("Please end block for {} with the 'end' token.", name))?;
+
+            match token.token_type {
+                TokenType::End => break,
+                TokenType::Number => values.push(Parser::convert_to_f32(token.value)?),
+                _ => return Err(format!("{} -> Expected a number in {} but received: {}", token.info, name, token.value).into()),
+            }
+        }
+
+        if !values.len().is_multiple_of(3) {
+            return Err(format!("Points for {} must come in groups of x y z but received {} numbers.", name, values.len()).into());
+        }
+
+        if values.len() < 6 {
+            return Err(format!("{} needs at least 2 points.", name).into());
+        }
+
+        Ok(values.chunks(3).map(|point| [point[0], point[1], point[2]]).collect())
     }
 
     fn handle_polygon(&mut self) -> Result<Command, Box<dyn Error>> {
@@ -280,9 +563,10 @@ impl Parser {
         let y = Parser::convert_to_f32(self.pop()?.value)?;
         let z = Parser::convert_to_f32(self.pop()?.value)?;
         let r = Parser::convert_to_f32(self.pop()?.value)?;
+        let steps = self.pop_optional_steps()?;
         let coord_system = self.pop_optional_type(TokenType::Identifier);
 
-        Ok(Command::Sphere { constants, x, y, z, r, coord_system })
+        Ok(Command::Sphere { constants, x, y, z, r, steps, coord_system })
     }
 
     fn handle_torus(&mut self) -> Result<Command, Box<dyn Error>> {
@@ -292,9 +576,10 @@ impl Parser {
         let z = Parser::convert_to_f32(self.pop()?.value)?;
         let r0 = Parser::convert_to_f32(self.pop()?.value)?;
         let r1 = Parser::convert_to_f32(self.pop()?.value)?;
+        let steps = self.pop_optional_steps()?;
         let coord_system = self.pop_optional_type(TokenType::Identifier);
 
-        Ok(Command::Torus { constants, x, y, z, r0, r1, coord_system })
+        Ok(Command::Torus { constants, x, y, z, r0, r1, steps, coord_system })
     }
 
     fn handle_cylinder(&mut self) -> Result<Command, Box<dyn Error>> {
@@ -304,9 +589,10 @@ impl Parser {
         let z = Parser::convert_to_f32(self.pop()?.value)?;
         let r = Parser::convert_to_f32(self.pop()?.value)?;
         let h = Parser::convert_to_f32(self.pop()?.value)?;
+        let steps = self.pop_optional_steps()?;
         let coord_system = self.pop_optional_type(TokenType::Identifier);
 
-        Ok(Command::Cylinder { constants, x, y, z, r, h, coord_system })
+        Ok(Command::Cylinder { constants, x, y, z, r, h, steps, coord_system })
     }
 
     fn handle_cone(&mut self) -> Result<Command, Box<dyn Error>> {
@@ -316,77 +602,505 @@ impl Parser {
         let z = Parser::convert_to_f32(self.pop()?.value)?;
         let r = Parser::convert_to_f32(self.pop()?.value)?;
         let h = Parser::convert_to_f32(self.pop()?.value)?;
+        let steps = self.pop_optional_steps()?;
         let coord_system = self.pop_optional_type(TokenType::Identifier);
 
-        Ok(Command::Cone { constants, x, y, z, r, h, coord_system })
+        Ok(Command::Cone { constants, x, y, z, r, h, steps, coord_system })
     }
 
-    fn handle_mesh(&mut self) -> Result<Command, Box<dyn Error>> {
+    fn handle_ellipsoid(&mut self) -> Result<Command, Box<dyn Error>> {
         let constants = self.pop_optional_type(TokenType::Identifier);
-        let file_path = self.pop_expected(TokenType::FilePath)?.value;
+        let x = Parser::convert_to_f32(self.pop()?.value)?;
+        let y = Parser::convert_to_f32(self.pop()?.value)?;
+        let z = Parser::convert_to_f32(self.pop()?.value)?;
+        let rx = Parser::convert_to_f32(self.pop()?.value)?;
+        let ry = Parser::convert_to_f32(self.pop()?.value)?;
+        let rz = Parser::convert_to_f32(self.pop()?.value)?;
+        let steps = self.pop_optional_steps()?;
         let coord_system = self.pop_optional_type(TokenType::Identifier);
 
-        Ok(Command::Mesh { constants, file_path, coord_system }) 
+        Ok(Command::Ellipsoid { constants, x, y, z, rx, ry, rz, steps, coord_system })
     }
 
-    fn handle_add_light(&mut self) -> Result<Command, Box<dyn Error>> {
+    fn handle_capsule(&mut self) -> Result<Command, Box<dyn Error>> {
+        let constants = self.pop_optional_type(TokenType::Identifier);
+        let x = Parser::convert_to_f32(self.pop()?.value)?;
+        let y = Parser::convert_to_f32(self.pop()?.value)?;
+        let z = Parser::convert_to_f32(self.pop()?.value)?;
         let r = Parser::convert_to_f32(self.pop()?.value)?;
-        let g = Parser::convert_to_f32(self.pop()?.value)?;
-        let b = Parser::convert_to_f32(self.pop()?.value)?;
+        let h = Parser::convert_to_f32(self.pop()?.value)?;
+        let steps = self.pop_optional_steps()?;
+        let coord_system = self.pop_optional_type(TokenType::Identifier);
+
+        Ok(Command::Capsule { constants, x, y, z, r, h, steps, coord_system })
+    }
+
+    fn handle_frustum(&mut self) -> Result<Command, Box<dyn Error>> {
+        let constants = self.pop_optional_type(TokenType::Identifier);
+        let x = Parser::convert_to_f32(self.pop()?.value)?;
+        let y = Parser::convert_to_f32(self.pop()?.value)?;
+        let z = Parser::convert_to_f32(self.pop()?.value)?;
+        let r0 = Parser::convert_to_f32(self.pop()?.value)?;
+        let r1 = Parser::convert_to_f32(self.pop()?.value)?;
+        let h = Parser::convert_to_f32(self.pop()?.value)?;
+        let steps = self.pop_optional_steps()?;
+        let coord_system = self.pop_optional_type(TokenType::Identifier);
+
+        Ok(Command::Frustum { constants, x, y, z, r0, r1, h, steps, coord_system })
+    }
+
+    fn handle_plane(&mut self) -> Result<Command, Box<dyn Error>> {
+        let constants = self.pop_optional_type(TokenType::Identifier);
         let x = Parser::convert_to_f32(self.pop()?.value)?;
         let y = Parser::convert_to_f32(self.pop()?.value)?;
         let z = Parser::convert_to_f32(self.pop()?.value)?;
+        let w = Parser::convert_to_f32(self.pop()?.value)?;
+        let d = Parser::convert_to_f32(self.pop()?.value)?;
+        let steps = self.pop_optional_steps()?;
+        let coord_system = self.pop_optional_type(TokenType::Identifier);
+
+        Ok(Command::Plane { constants, x, y, z, w, d, steps, coord_system })
+    }
+
+    fn handle_disc(&mut self) -> Result<Command, Box<dyn Error>> {
+        let constants = self.pop_optional_type(TokenType::Identifier);
+        let x = Parser::convert_to_f32(self.pop()?.value)?;
+        let y = Parser::convert_to_f32(self.pop()?.value)?;
+        let z = Parser::convert_to_f32(self.pop()?.value)?;
+        let r = Parser::convert_to_f32(self.pop()?.value)?;
+        let steps = self.pop_optional_steps()?;
+        let coord_system = self.pop_optional_type(TokenType::Identifier);
+
+        Ok(Command::Disc { constants, x, y, z, r, steps, coord_system })
+    }
+
+    fn handle_annulus(&mut self) -> Result<Command, Box<dyn Error>> {
+        let constants = self.pop_optional_type(TokenType::Identifier);
+        let x = Parser::convert_to_f32(self.pop()?.value)?;
+        let y = Parser::convert_to_f32(self.pop()?.value)?;
+        let z = Parser::convert_to_f32(self.pop()?.value)?;
+        let r0 = Parser::convert_to_f32(self.pop()?.value)?;
+        let r1 = Parser::convert_to_f32(self.pop()?.value)?;
+        let steps = self.pop_optional_steps()?;
+        let coord_system = self.pop_optional_type(TokenType::Identifier);
+
+        if r1 >= r0 {
+            return Err(format!("Annulus inner radius must be smaller than outer radius: {} >= {}", r1, r0).into());
+        }
+
+        Ok(Command::Annulus { constants, x, y, z, r0, r1, steps, coord_system })
+    }
+
+    fn handle_prism(&mut self) -> Result<Command, Box<dyn Error>> {
+        let constants = self.pop_optional_type(TokenType::Identifier);
+        let x = Parser::convert_to_f32(self.pop()?.value)?;
+        let y = Parser::convert_to_f32(self.pop()?.value)?;
+        let z = Parser::convert_to_f32(self.pop()?.value)?;
+        let r = Parser::convert_to_f32(self.pop()?.value)?;
+        let h = Parser::convert_to_f32(self.pop()?.value)?;
+        let sides = Parser::convert_to_steps(self.pop_expected(TokenType::Number)?.value)?;
+        let coord_system = self.pop_optional_type(TokenType::Identifier);
+
+        Ok(Command::Prism { constants, x, y, z, r, h, sides, coord_system })
+    }
+
+    fn handle_pyramid(&mut self) -> Result<Command, Box<dyn Error>> {
+        let constants = self.pop_optional_type(TokenType::Identifier);
+        let x = Parser::convert_to_f32(self.pop()?.value)?;
+        let y = Parser::convert_to_f32(self.pop()?.value)?;
+        let z = Parser::convert_to_f32(self.pop()?.value)?;
+        let r = Parser::convert_to_f32(self.pop()?.value)?;
+        let h = Parser::convert_to_f32(self.pop()?.value)?;
+        let sides = Parser::convert_to_steps(self.pop_expected(TokenType::Number)?.value)?;
+        let coord_system = self.pop_optional_type(TokenType::Identifier);
+
+        Ok(Command::Pyramid { constants, x, y, z, r, h, sides, coord_system })
+    }
+
+    fn handle_icosphere(&mut self) -> Result<Command, Box<dyn Error>> {
+        let constants = self.pop_optional_type(TokenType::Identifier);
+        let x = Parser::convert_to_f32(self.pop()?.value)?;
+        let y = Parser::convert_to_f32(self.pop()?.value)?;
+        let z = Parser::convert_to_f32(self.pop()?.value)?;
+        let r = Parser::convert_to_f32(self.pop()?.value)?;
+        let subdivisions = match self.pop_optional_type(TokenType::Number) {
+            Some(value) => Parser::convert_to_usize(value)?,
+            None => DEFAULT_ICOSPHERE_SUBDIVISIONS,
+        };
+        let coord_system = self.pop_optional_type(TokenType::Identifier);
+
+        if subdivisions > MAX_ICOSPHERE_SUBDIVISIONS {
+            return Err(format!("Icosphere subdivisions can't be more than {}: {}", MAX_ICOSPHERE_SUBDIVISIONS, subdivisions).into());
+        }
+
+        Ok(Command::Icosphere { constants, x, y, z, r, subdivisions, coord_system })
+    }
+
+    fn handle_lathe(&mut self) -> Result<Command, Box<dyn Error>> {
+        let constants = self.pop_optional_type(TokenType::Identifier);
+        let axis_str = self.pop_expected(TokenType::AxisOfRotation)?.value.to_lowercase();
+        let axis = match axis_str.as_str() {
+            "x" => Rotation::X,
+            "y" => Rotation::Y,
+            _ => return Err(format!("Lathe can only spin around the x or y axis: {}", axis_str).into()),
+        };
+        let curve = self.handle_curve()?;
+        let steps = self.pop_optional_steps()?;
+        let coord_system = self.pop_optional_type(TokenType::Identifier);
+
+        Ok(Command::Lathe { constants, axis, curve, steps, coord_system })
+    }
+
+    fn handle_extrude(&mut self) -> Result<Command, Box<dyn Error>> {
+        let constants = self.pop_optional_type(TokenType::Identifier);
+        let path = match self.pop_optional_type(TokenType::Number) {
+            Some(depth) => {
+                let depth = Parser::convert_to_f32(depth)?;
+                // the front and back would be on top of each other with nothing to sweep along
+                if depth == 0.0 {
+                    return Err("Extrude depth can't be 0.".into());
+                }
+                ExtrudePath::Depth(depth)
+            }
+            None => ExtrudePath::Curve(self.handle_curve()?),
+        };
+        let steps = self.pop_optional_steps()?;
+        let outline = self.handle_block("extrude")?;
+        let coord_system = self.pop_optional_type(TokenType::Identifier);
+
+        for command in &outline {
+            if !matches!(command, Command::Line { .. } | Command::Circle { .. } | Command::Hermite { .. } | Command::Bezier { .. }) {
+                return Err(format!("Only line, circle, hermite, and bezier can be used in an extrude outline: {:?}", command).into());
+            }
+        }
+
+        Ok(Command::Extrude { constants, path, steps, outline, coord_system })
+    }
+
+    fn handle_curve(&mut self) -> Result<Curve, Box<dyn Error>> {
+        let token = self.pop()?;
+
+        match token.token_type {
+            TokenType::Command(Function::Hermite) => {
+                let x0 = Parser::convert_to_f32(self.pop()?.value)?;
+                let y0 = Parser::convert_to_f32(self.pop()?.value)?;
+                let x1 = Parser::convert_to_f32(self.pop()?.value)?;
+                let y1 = Parser::convert_to_f32(self.pop()?.value)?;
+                let rx0 = Parser::convert_to_f32(self.pop()?.value)?;
+                let ry0 = Parser::convert_to_f32(self.pop()?.value)?;
+                let rx1 = Parser::convert_to_f32(self.pop()?.value)?;
+                let ry1 = Parser::convert_to_f32(self.pop()?.value)?;
+
+                Ok(Curve::Hermite { x0, y0, x1, y1, rx0, ry0, rx1, ry1 })
+            }
+            TokenType::Command(Function::Bezier) => {
+                let x0 = Parser::convert_to_f32(self.pop()?.value)?;
+                let y0 = Parser::convert_to_f32(self.pop()?.value)?;
+                let x1 = Parser::convert_to_f32(self.pop()?.value)?;
+                let y1 = Parser::convert_to_f32(self.pop()?.value)?;
+                let x2 = Parser::convert_to_f32(self.pop()?.value)?;
+                let y2 = Parser::convert_to_f32(self.pop()?.value)?;
+                let x3 = Parser::convert_to_f32(self.pop()?.value)?;
+                let y3 = Parser::convert_to_f32(self.pop()?.value)?;
+
+                Ok(Curve::Bezier { x0, y0, x1, y1, x2, y2, x3, y3 })
+            }
+            _ => Err(format!("{} -> Expected hermite or bezier but received: {}", token.info, token.value).into()),
+        }
+    }
+
+    fn handle_mesh(&mut self) -> Result<Command, Box<dyn Error>> {
+        let constants = self.pop_optional_type(TokenType::Identifier);
+        let file_path = self.pop_expected(TokenType::FilePath)?.value;
+        let coord_system = self.pop_optional_type(TokenType::Identifier);
+
+        Ok(Command::Mesh { constants, file_path, coord_system }) 
+    }
+
+    fn handle_add_light(&mut self) -> Result<Command, Box<dyn Error>> {
+        let r = self.pop_parameter()?;
+        let g = self.pop_parameter()?;
+        let b = self.pop_parameter()?;
+        let x = self.pop_parameter()?;
+        let y = self.pop_parameter()?;
+        let z = self.pop_parameter()?;
 
         Ok(Command::AddLight { r, g, b, x, y, z })
     }
 
     fn handle_set_ambient(&mut self) -> Result<Command, Box<dyn Error>> {
-        let r = Parser::convert_to_f32(self.pop()?.value)?;
-        let g = Parser::convert_to_f32(self.pop()?.value)?;
-        let b = Parser::convert_to_f32(self.pop()?.value)?;
+        let r = self.pop_parameter()?;
+        let g = self.pop_parameter()?;
+        let b = self.pop_parameter()?;
 
         Ok(Command::SetAmbient { r, g, b })
     }
 
     fn handle_define_constants(&mut self) -> Result<Command, Box<dyn Error>> {
         let name = self.pop_expected(TokenType::Identifier)?.value;
-        let kar = Parser::convert_to_f32(self.pop()?.value)?;
-        let kdr = Parser::convert_to_f32(self.pop()?.value)?;
-        let ksr = Parser::convert_to_f32(self.pop()?.value)?;
-        let kag = Parser::convert_to_f32(self.pop()?.value)?;
-        let kdg = Parser::convert_to_f32(self.pop()?.value)?;
-        let ksg = Parser::convert_to_f32(self.pop()?.value)?;
-        let kab = Parser::convert_to_f32(self.pop()?.value)?;
-        let kdb = Parser::convert_to_f32(self.pop()?.value)?;
-        let ksb = Parser::convert_to_f32(self.pop()?.value)?;
-
-        Ok(Command::DefineConstants { name, kar, kdr, ksr, kag, kdg, ksg, kab, kdb, ksb })
+        let kar = self.pop_parameter()?;
+        let kdr = self.pop_parameter()?;
+        let ksr = self.pop_parameter()?;
+        let kag = self.pop_parameter()?;
+        let kdg = self.pop_parameter()?;
+        let ksg = self.pop_parameter()?;
+        let kab = self.pop_parameter()?;
+        let kdb = self.pop_parameter()?;
+        let ksb = self.pop_parameter()?;
+        let opacity = self.pop_optional_parameter()?.map(Box::new);
+
+        Ok(Command::DefineConstants { name, kar, kdr, ksr, kag, kdg, ksg, kab, kdb, ksb, opacity })
     }
 
     fn handle_set_shading(&mut self) -> Result<Command, Box<dyn Error>> {
         let mode_str = self.pop_expected(TokenType::Identifier)?.value.to_lowercase();
         let shading_mode = match mode_str.as_str() {
             "wireframe" => ShadingMode::Wireframe,
+            "flat_random" => ShadingMode::FlatRandom,
             "flat" => ShadingMode::Flat,
             "gouraud" => ShadingMode::Gouraud,
             "phong" => ShadingMode::Phong,
+            "toon" => {
+                let bands = match self.pop_optional_type(TokenType::Number) {
+                    Some(bands) => Parser::convert_to_usize(bands)?,
+                    None => DEFAULT_TOON_BANDS,
+                };
+
+                if bands == 0 {
+                    return Err("Toon shading needs at least 1 band.".into());
+                }
+
+                ShadingMode::Toon(bands)
+            }
+            "normals" => ShadingMode::Normals,
+            "depth" => ShadingMode::Depth,
+            "uv" => ShadingMode::UvChecker,
+            "wireframe_shaded" => ShadingMode::WireframeShaded,
+            "back_faces" => ShadingMode::BackFaces,
             _ => return Err(format!("Invalid shading mode: {}", mode_str).into()),
         };
 
         Ok(Command::SetShading { shading_mode })
     }
 
+    fn handle_set_png_compression(&mut self) -> Result<Command, Box<dyn Error>> {
+        let level_str = self.pop_expected(TokenType::Identifier)?.value.to_lowercase();
+        let compression = match level_str.as_str() {
+            "fast" => png::Compression::Fast,
+            "balanced" => png::Compression::Default,
+            "high" => png::Compression::Best,
+            _ => return Err(format!("Invalid png compression: {}", level_str).into()),
+        };
+
+        Ok(Command::SetPngCompression { compression })
+    }
+
+    fn handle_set_tone_mapping(&mut self) -> Result<Command, Box<dyn Error>> {
+        let mode_str = self.pop_expected(TokenType::Identifier)?.value.to_lowercase();
+        let tone_mapping = match mode_str.as_str() {
+            "none" => ToneMapping::None,
+            "reinhard" => ToneMapping::Reinhard,
+            "aces" => ToneMapping::Aces,
+            "filmic" => ToneMapping::Filmic,
+            _ => return Err(format!("Invalid tone mapping: {}", mode_str).into()),
+        };
+
+        Ok(Command::SetToneMapping { tone_mapping })
+    }
+
+    fn handle_set_exposure(&mut self) -> Result<Command, Box<dyn Error>> {
+        let stops = self.pop_parameter()?;
+
+        Ok(Command::SetExposure { stops })
+    }
+
+    fn handle_set_gamma(&mut self) -> Result<Command, Box<dyn Error>> {
+        let gamma_str = self.pop_expected(TokenType::Identifier)?.value.to_lowercase();
+        let srgb = match gamma_str.as_str() {
+            "linear" => false,
+            "srgb" => true,
+            _ => return Err(format!("Invalid gamma: {}", gamma_str).into()),
+        };
+
+        Ok(Command::SetGamma { srgb })
+    }
+
+    fn handle_set_background(&mut self) -> Result<Command, Box<dyn Error>> {
+        if let Some(token) = self.stack.front() && token.token_type == TokenType::Identifier && token.value.eq_ignore_ascii_case("transparent") {
+            self.pop()?;
+            return Ok(Command::SetBackground { color: None });
+        }
+
+        let r = self.pop_parameter()?;
+        let g = self.pop_parameter()?;
+        let b = self.pop_parameter()?;
+
+        Ok(Command::SetBackground { color: Some((r, g, b)) })
+    }
+
+    fn handle_set_outline(&mut self) -> Result<Command, Box<dyn Error>> {
+        if let Some(token) = self.stack.front() && token.token_type == TokenType::Identifier && token.value.eq_ignore_ascii_case("none") {
+            self.pop()?;
+            return Ok(Command::SetOutline { width: None, color: None });
+        }
+
+        let width = self.pop_parameter()?;
+        let color = match self.pop_optional_parameter()? {
+            Some(r) => Some((r, self.pop_parameter()?, self.pop_parameter()?)),
+            None => None,
+        };
+
+        Ok(Command::SetOutline { width: Some(width), color })
+    }
+
+    fn handle_set_outline_creases(&mut self) -> Result<Command, Box<dyn Error>> {
+        let token = self.pop()?;
+
+        let degrees = match token.token_type {
+            TokenType::Identifier if token.value.eq_ignore_ascii_case("none") => None,
+            _ => Some(Parser::convert_to_f32(token.value)?),
+        };
+
+        Ok(Command::SetOutlineCreases { degrees })
+    }
+
+    fn handle_set_culling(&mut self) -> Result<Command, Box<dyn Error>> {
+        let mode_str = self.pop_expected(TokenType::Identifier)?.value.to_lowercase();
+        let mode = match mode_str.as_str() {
+            "back" => CullMode::Back,
+            "front" => CullMode::Front,
+            "none" => CullMode::None,
+            _ => return Err(format!("Invalid cull mode: {}", mode_str).into()),
+        };
+
+        Ok(Command::SetCulling { mode })
+    }
+
+    fn handle_set_depth_compare(&mut self) -> Result<Command, Box<dyn Error>> {
+        let compare_str = self.pop_expected(TokenType::Identifier)?.value.to_lowercase();
+        let compare = match compare_str.as_str() {
+            "closer" => DepthCompare::Closer,
+            "closer_equal" => DepthCompare::CloserOrEqual,
+            "farther" => DepthCompare::Farther,
+            "farther_equal" => DepthCompare::FartherOrEqual,
+            "equal" => DepthCompare::Equal,
+            _ => return Err(format!("Invalid depth comparison: {}", compare_str).into()),
+        };
+
+        Ok(Command::SetDepthCompare { compare })
+    }
+
+    fn handle_set_passes(&mut self) -> Result<Command, Box<dyn Error>> {
+        // any number of passes, or none to stop saving them
+        let mut passes = vec![];
+
+        while let Some(pass_str) = self.pop_optional_type(TokenType::Identifier) {
+            match pass_str.to_lowercase().as_str() {
+                "depth" => passes.push(Pass::Depth),
+                "normal" => passes.push(Pass::Normal),
+                "id" => passes.push(Pass::Id),
+                "none" => passes.clear(),
+                _ => return Err(format!("Invalid pass: {}", pass_str).into()),
+            }
+        }
+
+        Ok(Command::SetPasses { passes })
+    }
+
+    fn handle_blur(&mut self) -> Result<Command, Box<dyn Error>> {
+        let radius = self.pop_parameter()?;
+
+        Ok(Command::Blur { radius })
+    }
+
+    fn handle_bloom(&mut self) -> Result<Command, Box<dyn Error>> {
+        let threshold = self.pop_parameter()?;
+        let strength = self.pop_parameter()?;
+        let radius = self.pop_parameter()?;
+
+        Ok(Command::Bloom { threshold, strength, radius })
+    }
+
+    fn handle_vignette(&mut self) -> Result<Command, Box<dyn Error>> {
+        let strength = self.pop_parameter()?;
+
+        Ok(Command::Vignette { strength })
+    }
+
+    fn handle_grade(&mut self) -> Result<Command, Box<dyn Error>> {
+        let brightness = self.pop_parameter()?;
+        let contrast = self.pop_parameter()?;
+        let saturation = self.pop_parameter()?;
+
+        Ok(Command::Grade { brightness, contrast, saturation })
+    }
+
+    fn handle_lut(&mut self) -> Result<Command, Box<dyn Error>> {
+        let file_path = self.pop_expected(TokenType::FilePath)?.value;
+
+        Ok(Command::Lut { file_path })
+    }
+
+    fn handle_depth_of_field(&mut self) -> Result<Command, Box<dyn Error>> {
+        let focus = self.pop_parameter()?;
+        let range = self.pop_parameter()?;
+        let radius = self.pop_parameter()?;
+
+        Ok(Command::DepthOfField { focus, range, radius })
+    }
+
+    fn handle_edge_detect(&mut self) -> Result<Command, Box<dyn Error>> {
+        let threshold = self.pop_parameter()?;
+
+        // the outline color is optional (black by default) but has to be all three if it's there
+        let color = match self.pop_optional_parameter()? {
+            Some(r) => Some((r, self.pop_parameter()?, self.pop_parameter()?)),
+            None => None,
+        };
+
+        Ok(Command::EdgeDetect { threshold, color })
+    }
+
+    fn handle_set_crease_angle(&mut self) -> Result<Command, Box<dyn Error>> {
+        let degrees = Parser::convert_to_f32(self.pop_expected(TokenType::Number)?.value)?;
+
+        Ok(Command::SetCreaseAngle { degrees })
+    }
+
     fn handle_set_camera(&mut self) -> Result<Command, Box<dyn Error>> {
-        let eye_x = Parser::convert_to_f32(self.pop()?.value)?;
-        let eye_y = Parser::convert_to_f32(self.pop()?.value)?;
-        let eye_z = Parser::convert_to_f32(self.pop()?.value)?;
-        let aim_x = Parser::convert_to_f32(self.pop()?.value)?;
-        let aim_y = Parser::convert_to_f32(self.pop()?.value)?;
-        let aim_z = Parser::convert_to_f32(self.pop()?.value)?;
+        let eye_x = self.pop_parameter()?;
+        let eye_y = self.pop_parameter()?;
+        let eye_z = self.pop_parameter()?;
+        let aim_x = self.pop_parameter()?;
+        let aim_y = self.pop_parameter()?;
+        let aim_z = self.pop_parameter()?;
 
         Ok(Command::SetCamera { eye_x, eye_y, eye_z, aim_x, aim_y, aim_z })
     }
 
+    fn handle_set_tessellation(&mut self) -> Result<Command, Box<dyn Error>> {
+        let token = self.pop()?;
+
+        let tessellation = match token.token_type {
+            TokenType::Number => Tessellation::Fixed(Parser::convert_to_steps(token.value)?),
+            TokenType::Identifier if token.value.to_lowercase() == "adaptive" => {
+                let segment_length = match self.pop_optional_type(TokenType::Number) {
+                    Some(value) => Parser::convert_to_f32(value)?,
+                    None => DEFAULT_ADAPTIVE_SEGMENT_LENGTH,
+                };
+
+                if segment_length <= 0.0 {
+                    return Err(format!("Adaptive segment length must be positive: {}", segment_length).into());
+                }
+
+                Tessellation::Adaptive(segment_length)
+            }
+            _ => return Err(format!("Invalid tessellation: {}", token.value).into()),
+        };
+
+        Ok(Command::SetTessellation { tessellation })
+    }
+
     fn handle_set_base_name(&mut self) -> Result<Command, Box<dyn Error>> {
         let name = self.pop_expected(TokenType::Identifier)?.value;
 
@@ -402,16 +1116,82 @@ impl Parser {
 
     fn handle_save_knob_list(&mut self) -> Result<Command, Box<dyn Error>> {
         let name = self.pop_expected(TokenType::Identifier)?.value;
+        let frame = match self.stack.front() {
+            Some(token) if token.token_type == TokenType::Number || token.token_type == TokenType::Time => Some(Parser::convert_to_time(self.pop()?)?),
+            _ => None,
+        };
+
+        Ok(Command::SaveKnobList { name, frame })
+    }
+
+    fn handle_blend_knobs(&mut self) -> Result<Command, Box<dyn Error>> {
+        // blend_knobs name begin knoblist weight ... end
+        let name = self.pop_expected(TokenType::Identifier)?.value;
+        let _ = self.pop_expected(TokenType::Begin)?;
+        let mut lists: Vec<(String, f32)> = vec![];
+
+        loop {
+            let token = self.pop().map_err(|_| format!("Please end block for blend_knobs {} with the 'end' token.", name))?;
 
-        Ok(Command::SaveKnobList { name })
+            if token.token_type == TokenType::End {
+                break;
+            }
+
+            if token.token_type != TokenType::Identifier {
+                return Err(format!("{} -> Expected a knoblist in blend_knobs {} but received: {}", token.info, name, token.value).into());
+            }
+
+            let weight = Parser::convert_to_f32(self.pop_expected(TokenType::Number)?.value)?;
+            lists.push((token.value, weight));
+        }
+
+        if lists.iter().map(|(_, weight)| weight).sum::<f32>() == 0.0 {
+            return Err(format!("The weights in blend_knobs {} add up to 0.", name).into());
+        }
+
+        Ok(Command::BlendKnobs { name, lists })
+    }
+
+    fn handle_layer(&mut self) -> Result<Command, Box<dyn Error>> {
+        // layer [add | blend weight] [knobs...] begin ... end
+        let mode = match self.stack.front() {
+            Some(token) if token.token_type == TokenType::Identifier && token.value == "add" => {
+                self.pop()?;
+                LayerMode::Add
+            }
+            Some(token) if token.token_type == TokenType::Identifier && token.value == "blend" => {
+                self.pop()?;
+                LayerMode::Blend(self.pop_parameter()?)
+            }
+            _ => LayerMode::Blend(Parameter::Number(1.0)),
+        };
+
+        let mut mask: Vec<String> = vec![];
+        while let Some(knob) = self.pop_optional_type(TokenType::Identifier) {
+            mask.push(knob);
+        }
+
+        let commands = self.handle_block("layer")?;
+
+        // the layer only makes its own knobs so anything that draws wouldn't do anything in here
+        let animates_knobs = |command: &Command| matches!(command,
+            Command::VaryKnob { .. } | Command::Keyframes { .. } | Command::Tween { .. } | Command::SaveKnobList { .. } |
+            Command::BlendKnobs { .. } | Command::Wiggle { .. } | Command::Oscillate { .. } | Command::LoopKnob { .. } |
+            Command::KnobExpression { .. } | Command::Layer { .. });
+
+        if !commands.iter().all(animates_knobs) {
+            return Err("Only commands that animate knobs (vary, keyframes, tween, wiggle, ...) can go in a layer.".into());
+        }
+
+        Ok(Command::Layer { mode, mask, commands })
     }
 
     fn handle_tween(&mut self) -> Result<Command, Box<dyn Error>> {
-        let start_frame = Parser::convert_to_usize(self.pop_expected(TokenType::Number)?.value)?;
-        let end_frame = Parser::convert_to_usize(self.pop_expected(TokenType::Number)?.value)?;
+        let start_frame = Parser::convert_to_time(self.pop()?)?;
+        let end_frame = Parser::convert_to_time(self.pop()?)?;
         let knoblist0 = self.pop_expected(TokenType::Identifier)?.value;
         let knoblist1 = self.pop_expected(TokenType::Identifier)?.value;
-        let easing = self.pop_optional_type(TokenType::EasingFunction);
+        let easing = self.pop_optional_easing()?;
 
         Ok(Command::Tween { start_frame, end_frame, knoblist0, knoblist1, easing })
     }
@@ -422,13 +1202,82 @@ impl Parser {
         Ok(Command::SetFrames { num_frames })
     }
 
+    fn handle_set_frame_rate(&mut self) -> Result<Command, Box<dyn Error>> {
+        let fps = Parser::convert_to_f32(self.pop_expected(TokenType::Number)?.value)?;
+
+        if fps <= 0.0 {
+            return Err(format!("Frame rate must be positive: {}", fps).into());
+        }
+
+        Ok(Command::SetFrameRate { fps })
+    }
+
+    fn handle_set_duration(&mut self) -> Result<Command, Box<dyn Error>> {
+        // duration 2.5 and duration 2.5s both mean seconds
+        let token = self.pop()?;
+        if token.token_type != TokenType::Number && token.token_type != TokenType::Time {
+            return Err(format!("{} -> Expected a duration in seconds but received: {}", token.info, token.value).into());
+        }
+
+        let seconds = Parser::convert_to_f32(token.value)?;
+
+        if seconds <= 0.0 {
+            return Err(format!("Duration must be positive: {}", seconds).into());
+        }
+
+        Ok(Command::SetDuration { seconds })
+    }
+
+    fn handle_set_motion_blur(&mut self) -> Result<Command, Box<dyn Error>> {
+        let samples = Parser::convert_to_usize(self.pop_expected(TokenType::Number)?.value)?;
+        // 180 degrees is what film cameras usually use, the shutter is open for half of each frame
+        let shutter_angle = match self.pop_optional_type(TokenType::Number) {
+            Some(value) => Parser::convert_to_f32(value)?,
+            None => 180.0,
+        };
+
+        if samples == 0 {
+            return Err("Motion blur needs at least 1 sample.".into());
+        }
+
+        if shutter_angle <= 0.0 || shutter_angle > 360.0 {
+            return Err(format!("Shutter angle must be between 0 and 360 degrees: {}", shutter_angle).into());
+        }
+
+        Ok(Command::SetMotionBlur { samples, shutter_angle })
+    }
+
+    fn handle_render_frames(&mut self) -> Result<Command, Box<dyn Error>> {
+        let start = Parser::convert_to_time(self.pop()?)?;
+        let end = Parser::convert_to_time(self.pop()?)?;
+
+        Ok(Command::RenderFrames { start, end })
+    }
+
+    fn handle_render_frame(&mut self) -> Result<Command, Box<dyn Error>> {
+        // just a range that starts and ends on the same frame
+        let frame = Parser::convert_to_time(self.pop()?)?;
+
+        Ok(Command::RenderFrames { start: frame, end: frame })
+    }
+
+    fn handle_render_every(&mut self) -> Result<Command, Box<dyn Error>> {
+        let every = Parser::convert_to_usize(self.pop_expected(TokenType::Number)?.value)?;
+
+        if every == 0 {
+            return Err("render_every needs a number of frames above 0.".into());
+        }
+
+        Ok(Command::RenderEvery { every })
+    }
+
     fn handle_vary_knob(&mut self) -> Result<Command, Box<dyn Error>> {
         let knob = self.pop_expected(TokenType::Identifier)?.value;
-        let start_frame = Parser::convert_to_usize(self.pop()?.value)?;
-        let end_frame = Parser::convert_to_usize(self.pop()?.value)?;
+        let start_frame = Parser::convert_to_time(self.pop()?)?;
+        let end_frame = Parser::convert_to_time(self.pop()?)?;
         let start_val = Parser::convert_to_f32(self.pop()?.value)?;
         let end_val = Parser::convert_to_f32(self.pop()?.value)?;
-        let easing = self.pop_optional_type(TokenType::EasingFunction);
+        let easing = self.pop_optional_easing()?;
 
         Ok(Command::VaryKnob { knob, start_frame, end_frame, start_val, end_val, easing })
     }
@@ -441,8 +1290,218 @@ impl Parser {
 
     fn handle_save_coord_system(&mut self) -> Result<Command, Box<dyn Error>> {
         let name = self.pop_expected(TokenType::Identifier)?.value;
+        let joint = self.pop_optional_type(TokenType::Identifier);
 
-        Ok(Command::SaveCoordSystem { name })
+        Ok(Command::SaveCoordSystem { name, joint })
+    }
+
+    fn handle_define_joint(&mut self) -> Result<Command, Box<dyn Error>> {
+        let name = self.pop_expected(TokenType::Identifier)?.value;
+        let parent = self.pop_optional_type(TokenType::Identifier);
+        let x = Parser::convert_to_f32(self.pop()?.value)?;
+        let y = Parser::convert_to_f32(self.pop()?.value)?;
+        let z = Parser::convert_to_f32(self.pop()?.value)?;
+
+        if parent.as_ref() == Some(&name) {
+            return Err(format!("Joint {} can't be its own parent.", name).into());
+        }
+
+        Ok(Command::DefineJoint { name, parent, x, y, z })
+    }
+
+    fn handle_pose_joint(&mut self) -> Result<Command, Box<dyn Error>> {
+        let name = self.pop_expected(TokenType::Identifier)?.value;
+        let axis_str = self.pop_expected(TokenType::AxisOfRotation)?.value.to_lowercase();
+        let axis = match axis_str.as_str() {
+            "x" => Rotation::X,
+            "y" => Rotation::Y,
+            "z" => Rotation::Z,
+            _ => return Err(format!("Invalid rotation axis: {}", axis_str).into()),
+        };
+        let degrees = Parser::convert_to_f32(self.pop()?.value)?;
+        let knob = self.pop_optional_type(TokenType::Identifier);
+
+        Ok(Command::PoseJoint { name, axis, degrees, knob })
+    }
+
+    fn handle_keyframes(&mut self) -> Result<Command, Box<dyn Error>> {
+        // keyframes knob begin frame value [interpolation] ... end
+        let knob = self.pop_expected(TokenType::Identifier)?.value;
+        let _ = self.pop_expected(TokenType::Begin)?;
+        let mut keys: Vec<Keyframe> = vec![];
+
+        loop {
+            let token = self.pop().map_err(|_| format!("Please end block for keyframes {} with the 'end' token.", knob))?;
+
+            if token.token_type == TokenType::End {
+                break;
+            }
+
+            if token.token_type != TokenType::Number && token.token_type != TokenType::Time {
+                return Err(format!("{} -> Expected a frame number or time in keyframes {} but received: {}", token.info, knob, token.value).into());
+            }
+
+            let frame = Parser::convert_to_time(token)?;
+            let value = Parser::convert_to_f32(self.pop_expected(TokenType::Number)?.value)?;
+
+            // the interpolation is whatever comes before the next frame number
+            // go by the text since some of the names are also commands (catmull_rom and bezier)
+            let interpolation = match self.stack.front() {
+                Some(token) if ![TokenType::Number, TokenType::Time, TokenType::End].contains(&token.token_type) => {
+                    let token = self.pop()?;
+
+                    match token.value.to_lowercase().as_str() {
+                        "step" => Interpolation::Step,
+                        "linear" => Interpolation::Linear,
+                        "catmull_rom" => Interpolation::CatmullRom,
+                        "bezier" => {
+                            let x1 = Parser::convert_to_f32(self.pop_expected(TokenType::Number)?.value)?;
+                            let y1 = Parser::convert_to_f32(self.pop_expected(TokenType::Number)?.value)?;
+                            let x2 = Parser::convert_to_f32(self.pop_expected(TokenType::Number)?.value)?;
+                            let y2 = Parser::convert_to_f32(self.pop_expected(TokenType::Number)?.value)?;
+
+                            Interpolation::Bezier { x1, y1, x2, y2 }
+                        }
+                        _ => return Err(format!("{} -> Invalid interpolation: {}", token.info, token.value).into()),
+                    }
+                }
+                _ => Interpolation::Linear,
+            };
+
+            keys.push(Keyframe { frame, value, interpolation });
+        }
+
+        if keys.is_empty() {
+            return Err(format!("Keyframes for {} needs at least 1 key.", knob).into());
+        }
+
+        Ok(Command::Keyframes { knob, keys })
+    }
+
+    fn handle_wiggle(&mut self) -> Result<Command, Box<dyn Error>> {
+        let knob = self.pop_expected(TokenType::Identifier)?.value;
+        let frequency = Parser::convert_to_f32(self.pop_expected(TokenType::Number)?.value)?;
+        let amplitude = Parser::convert_to_f32(self.pop_expected(TokenType::Number)?.value)?;
+        let seed = match self.pop_optional_type(TokenType::Number) {
+            Some(value) => value.parse::<u32>().map_err(|_| format!("Error parsing seed: {}", value))?,
+            None => 0,
+        };
+
+        Ok(Command::Wiggle { knob, frequency, amplitude, seed })
+    }
+
+    fn handle_oscillate(&mut self) -> Result<Command, Box<dyn Error>> {
+        let knob = self.pop_expected(TokenType::Identifier)?.value;
+        let wave_str = self.pop_expected(TokenType::Identifier)?.value.to_lowercase();
+        let wave = match wave_str.as_str() {
+            "sin" => Wave::Sine,
+            "triangle" => Wave::Triangle,
+            "square" => Wave::Square,
+            "saw" => Wave::Saw,
+            _ => return Err(format!("Invalid wave: {}", wave_str).into()),
+        };
+        let period = Parser::convert_to_time(self.pop()?)?;
+        let amplitude = Parser::convert_to_f32(self.pop_expected(TokenType::Number)?.value)?;
+        let phase = match self.pop_optional_type(TokenType::Number) {
+            Some(value) => Parser::convert_to_f32(value)?,
+            None => 0.0,
+        };
+
+        Ok(Command::Oscillate { knob, wave, period, amplitude, phase })
+    }
+
+    fn handle_loop_knob(&mut self, pingpong: bool) -> Result<Command, Box<dyn Error>> {
+        let knob = self.pop_expected(TokenType::Identifier)?.value;
+
+        Ok(Command::LoopKnob { knob, pingpong })
+    }
+
+    fn handle_knob_expression(&mut self) -> Result<Command, Box<dyn Error>> {
+        let knob = self.pop_expected(TokenType::Identifier)?.value;
+        let expression = self.pop_expression()?;
+
+        Ok(Command::KnobExpression { knob, expression })
+    }
+
+    fn pop_parameter(&mut self) -> Result<Parameter, Box<dyn Error>> {
+        let token = self.pop()?;
+
+        match token.token_type {
+            TokenType::Number => Ok(Parameter::Number(Parser::convert_to_f32(token.value)?)),
+            TokenType::Identifier => Ok(Parameter::Knob(token.value)),
+            TokenType::Symbol if token.value == "(" => {
+                self.stack.push_front(token);
+                Ok(Parameter::Expression(self.pop_expression()?))
+            }
+            _ => Err(format!("{} -> Expected a number, knob, or expression but received: {}", token.info, token.value).into()),
+        }
+    }
+
+    fn pop_optional_parameter(&mut self) -> Result<Option<Parameter>, Box<dyn Error>> {
+        // every command starts with a keyword so anything that could be a parameter after the required ones belongs to this command
+        match self.stack.front() {
+            Some(token) if matches!(token.token_type, TokenType::Number | TokenType::Identifier) || (token.token_type == TokenType::Symbol && token.value == "(") => {
+                Ok(Some(self.pop_parameter()?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn pop_expression(&mut self) -> Result<Expression, Box<dyn Error>> {
+        // everything from the opening parenthesis to the matching closing one
+        let open = self.pop()?;
+        if open.value != "(" {
+            return Err(format!("{} -> Expected an expression in parentheses but received: {}", open.info, open.value).into());
+        }
+
+        let mut text = String::from("(");
+        let mut depth = 1;
+
+        while depth > 0 {
+            let token = self.pop().map_err(|_| format!("{} -> Expression is missing a ')'.", open.info))?;
+
+            if token.token_type == TokenType::Symbol {
+                match token.value.as_str() {
+                    "(" => depth += 1,
+                    ")" => depth -= 1,
+                    _ => {}
+                }
+            }
+
+            // times would lose their s and quietly turn into frames, so expressions use frame, time, and fps instead
+            if token.token_type == TokenType::Time {
+                return Err(format!("{} -> Times like {}s can't be used in an expression, use frame, time (in seconds), or fps instead.", token.info, token.value).into());
+            }
+
+            // the lexer already split everything up so spaces between the pieces don't change anything
+            text.push(' ');
+            text.push_str(&token.value);
+        }
+
+        Expression::parse(&text).map_err(|error| format!("{} -> {}", open.info, error).into())
+    }
+
+    fn handle_follow_path(&mut self) -> Result<Command, Box<dyn Error>> {
+        let name = self.pop_expected(TokenType::Identifier)?.value;
+        let t = Parser::convert_to_f32(self.pop_expected(TokenType::Number)?.value)?;
+        let knob = self.pop_optional_type(TokenType::Identifier);
+
+        Ok(Command::FollowPath { name, t, knob })
+    }
+
+    fn handle_define_path(&mut self) -> Result<Command, Box<dyn Error>> {
+        let name = self.pop_expected(TokenType::Identifier)?.value;
+        let token = self.pop()?;
+        let info = token.info.clone();
+        let curve = self.get_command(token)?;
+
+        match curve {
+            Command::Line { .. } | Command::Circle { .. } | Command::Hermite { .. } | Command::Bezier { .. } |
+            Command::Hermite3D { .. } | Command::Bezier3D { .. } | Command::CatmullRom { .. } | Command::BSpline { .. } => {
+                Ok(Command::DefinePath { name, curve: Box::new(curve) })
+            }
+            _ => Err(format!("{} -> Path {} has to be a line, circle, hermite, bezier, hermite3d, bezier3d, catmull_rom, or bspline.", info, name).into()),
+        }
     }
 
     fn handle_set_focal_length(&mut self) -> Result<Command, Box<dyn Error>> {
@@ -453,6 +1512,12 @@ impl Parser {
 
     fn handle_create_composite(&mut self) -> Result<Command, Box<dyn Error>> {
         let name = self.pop_expected(TokenType::Identifier)?.value;
+        let commands = self.handle_block(&format!("composite command {}", name))?;
+
+        Ok(Command::CreateComposite { name, commands })
+    }
+
+    fn handle_block(&mut self, name: &str) -> Result<Vec<Command>, Box<dyn Error>> {
         let _ = self.pop_expected(TokenType::Begin)?;
         let mut commands: Vec<Command> = vec![];
         let mut found_end = false;
@@ -471,10 +1536,10 @@ impl Parser {
         }
 
         if !found_end {
-            return Err(format!("Please end block for composite command {} with the 'end' token.", name).into());
+            return Err(format!("Please end block for {} with the 'end' token.", name).into());
         }
 
-        Ok(Command::CreateComposite { name, commands })
+        Ok(commands)
     }
 
     fn handle_run_composite(&mut self) -> Result<Command, Box<dyn Error>> {
@@ -483,10 +1548,85 @@ impl Parser {
         Ok(Command::RunComposite { name  })
     }
 
+    fn pop_optional_easing(&mut self) -> Result<Option<Easing>, Box<dyn Error>> {
+        let Some(easing) = self.pop_optional_type(TokenType::EasingFunction) else {
+            return Ok(None);
+        };
+
+        let arguments: Vec<&str> = easing
+            .split_once('(')
+            .map(|(_, rest)| rest.trim_end_matches(')').split(',').map(str::trim).collect())
+            .unwrap_or_default();
+
+        if easing.starts_with("cubicBezier(") {
+            if arguments.len() != 4 {
+                return Err(format!("cubicBezier needs 4 numbers: {}", easing).into());
+            }
+
+            let x1 = Parser::convert_to_f32(arguments[0].to_string())?;
+            let y1 = Parser::convert_to_f32(arguments[1].to_string())?;
+            let x2 = Parser::convert_to_f32(arguments[2].to_string())?;
+            let y2 = Parser::convert_to_f32(arguments[3].to_string())?;
+
+            // x is time so it can't go backwards, y is allowed to overshoot
+            if !(0.0..=1.0).contains(&x1) || !(0.0..=1.0).contains(&x2) {
+                return Err(format!("cubicBezier x values must be between 0 and 1: {}", easing).into());
+            }
+
+            Ok(Some(Easing::CubicBezier { x1, y1, x2, y2 }))
+        } else if easing.starts_with("steps(") {
+            let steps = match arguments[..] {
+                [steps] => Parser::convert_to_usize(steps.to_string())?,
+                _ => return Err(format!("steps needs 1 number: {}", easing).into()),
+            };
+
+            if steps == 0 {
+                return Err(format!("steps needs at least 1 step: {}", easing).into());
+            }
+
+            Ok(Some(Easing::Steps(steps)))
+        } else {
+            Ok(Some(Easing::Named(easing)))
+        }
+    }
+
+    fn pop_optional_steps(&mut self) -> Result<Option<i32>, Box<dyn Error>> {
+        match self.pop_optional_type(TokenType::Number) {
+            Some(value) => Ok(Some(Parser::convert_to_steps(value)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn convert_to_steps(parameter: String) -> Result<i32, Box<dyn Error>> {
+        let steps = parameter.parse::<i32>().map_err(|_| format!("Error parsing steps: {}", parameter))?;
+
+        if !(MIN_PARAMETRIC_STEPS..=MAX_PARAMETRIC_STEPS).contains(&steps) {
+            return Err(format!("Steps must be between {} and {}: {}", MIN_PARAMETRIC_STEPS, MAX_PARAMETRIC_STEPS, steps).into());
+        }
+
+        Ok(steps)
+    }
+
     fn convert_to_f32(parameter: String) -> Result<f32, Box<dyn Error>> {
         parameter.parse::<f32>().map_err(|_| format!("Error parsing f32: {}", parameter).into())
     }
 
+    fn convert_to_time(token: Token) -> Result<Time, Box<dyn Error>> {
+        match token.token_type {
+            TokenType::Number => Ok(Time::Frame(Parser::convert_to_usize(token.value)?)),
+            TokenType::Time => {
+                let seconds = Parser::convert_to_f32(token.value)?;
+
+                if seconds < 0.0 {
+                    return Err(format!("{} -> Time can't be negative: {}s", token.info, seconds).into());
+                }
+
+                Ok(Time::Seconds(seconds))
+            }
+            _ => Err(format!("{} -> Expected a frame number or time but received: {}", token.info, token.value).into()),
+        }
+    }
+
     fn convert_to_usize(parameter: String) -> Result<usize, Box<dyn Error>> {
         parameter.parse::<usize>().map_err(|_| format!("Error parsing usize: {}", parameter).into())
     }
@@ -1,25 +1,36 @@
 #![allow(dead_code)]
 
 use std::{
-    collections::HashMap, error::Error, vec
+    collections::HashMap, error::Error, f32::consts::PI, time::Instant, vec
 };
 
 use crate::{
     constants::{
-        DEFAULT_ANIMATION_DELAY_MS, DEFAULT_BACKGROUND_COLOR, DEFAULT_FOREGROUND_COLOR, DEFAULT_PICTURE_DIMENSIONS, DEFAULT_REFLECTION_CONSTANTS, DEFAULT_SHADING_MODE, GENERATE_TEMPORARY_FRAME_FILES, ShadingMode
-    }, interpreter::animation::Animation, matrix, render::{
+        DEFAULT_BACKGROUND_COLOR, DEFAULT_CULL_MODE, DEFAULT_TWO_SIDED_LIGHTING, DEFAULT_FRAME_RATE, DEFAULT_PNG_COMPRESSION, DEFAULT_CREASE_ANGLE, DEFAULT_FOREGROUND_COLOR, DEFAULT_PICTURE_DIMENSIONS, DEFAULT_REFLECTION_CONSTANTS, DEFAULT_SHADING_MODE, DEFAULT_TESSELLATION, GENERATE_TEMPORARY_FRAME_FILES, CACHE_TEMPORARY_FRAME_FILES, MAX_PARAMETRIC_STEPS, MIN_PARAMETRIC_STEPS, ShadingMode, Tessellation
+    }, interpreter::animation::{Animation, AnimationSettings, FrameRange}, matrix, render::{
         LightingConfig,
+        Pass,
         Picture,
+        SaveOptions,
         ReflectionConstants,
-        edge_list::{add_bezier_curve, add_circle, add_edge, add_hermite_curve, render_edges},
-        polygon_list::{add_box, add_cone, add_cylinder, add_polygon, add_sphere, add_torus, render_polygons},
+        indexed_mesh::IndexedMesh,
+        edge_list::{
+            add_bezier_curve, add_circle, add_edge, add_hermite_curve, add_path, b_spline_points, bezier3d_points, bezier_points,
+            catmull_rom_points, circle_points, hermite3d_points, hermite_points, render_edges,
+        },
+        polygon_list::{
+            add_box, add_capsule, add_cone, add_cylinder, add_disc, add_ellipsoid, add_frustum, add_icosphere, add_lathe,
+            add_plane, add_polygon, add_prism, add_pyramid, add_sphere, add_sweep, add_torus, render_polygons, render_outline, Culling, Outline,
+        },
         texture::{MTL, render_textured_polygon},
+        post_process,
     }, vector::{cross_product, dot_product, normalize_vector, subtract_vectors}
 };
 use super::{
     coordinate_stack::CoordinateStack,
-    parser::Command,
+    parser::{Command, Curve, ExtrudePath, Parameter},
     animation,
+    frame_cache::FrameCache,
     mesh::handle_mesh,
 };
 
@@ -31,55 +42,129 @@ enum Symbol {
     Knob(f32),
     CoordSystem(Matrix),
     CompositeCommand(Vec<Command>),
+    Path(Vec<[f32; 3]>),
+    // base is the coordinate system the joint was made in, only used when it doesn't have a parent
+    // pose is every rotation the joint has been posed with so far
+    Joint { parent: Option<String>, base: Matrix, pivot: [f32; 3], pose: Matrix },
 }
 
 enum CachedMesh {
-    NoTexture(Matrix),
-    Texture((Matrix, Vec<(String, [[f32; 2]; 3])>, HashMap<String, MTL>)),
+    NoTexture(IndexedMesh),
+    Texture((IndexedMesh, Vec<usize>, Vec<MTL>)),
 }
 
 pub struct ScriptContext {
     picture: Picture,
     edges: Matrix,
-    polygons: Matrix,
+    polygons: IndexedMesh,
     coordinate_stack: CoordinateStack,
     shading_mode: ShadingMode,
+    crease_angle: f32,
+    culling: Culling,
+    tessellation: Tessellation,
     lighting_config: LightingConfig,
     reflection_constants: ReflectionConstants,
     camera_matrix: Matrix,
     symbols: HashMap<String, Symbol>,
     mesh_cache: HashMap<String, CachedMesh>,
+    // fractional while rendering motion blur samples
+    frame: f32,
+    fps: f32,
+    png_compression: png::Compression,
+    passes: Vec<Pass>,
+    outline: Option<Outline>,
+    // saved in png text chunks
+    script_path: String,
+    render_start: Instant,
 }
 
 impl ScriptContext {
-    pub fn new() -> Self {
+    pub fn new(script_path: &str) -> Self {
         Self {
             picture: Picture::new(DEFAULT_PICTURE_DIMENSIONS.0, DEFAULT_PICTURE_DIMENSIONS.1, 255, &DEFAULT_BACKGROUND_COLOR),
             edges: matrix::new(),
-            polygons: matrix::new(),
+            polygons: IndexedMesh::new(),
             coordinate_stack: CoordinateStack::new(),
             shading_mode: DEFAULT_SHADING_MODE,
-            lighting_config: LightingConfig {
-                ambient_light_color: [50.0, 50.0, 50.0],
-                point_lights: vec![[[255.0, 255.0, 255.0], normalize_vector(&[0.5, 0.75, 1.0])]],
-            },
+            crease_angle: DEFAULT_CREASE_ANGLE,
+            culling: Culling { mode: DEFAULT_CULL_MODE, two_sided: DEFAULT_TWO_SIDED_LIGHTING },
+            tessellation: DEFAULT_TESSELLATION,
+            lighting_config: default_lighting_config(),
             reflection_constants: DEFAULT_REFLECTION_CONSTANTS,
             camera_matrix: matrix::identity(),
             symbols: HashMap::new(),
             mesh_cache: HashMap::new(),
+            frame: 0.0,
+            fps: DEFAULT_FRAME_RATE,
+            png_compression: DEFAULT_PNG_COMPRESSION,
+            passes: vec![],
+            outline: None,
+            script_path: script_path.to_string(),
+            render_start: Instant::now(),
         }
     }
 
     fn frame_reset(&mut self) {
         self.picture = Picture::new(DEFAULT_PICTURE_DIMENSIONS.0, DEFAULT_PICTURE_DIMENSIONS.1, 255, &DEFAULT_BACKGROUND_COLOR);
         self.edges = matrix::new();
-        self.polygons = matrix::new();
+        self.polygons = IndexedMesh::new();
         self.coordinate_stack = CoordinateStack::new();
+
+        // the script sets these up again every frame, and they could be different if they use knobs
+        // (without this every frame would add another copy of each light)
+        self.lighting_config = default_lighting_config();
+        self.camera_matrix = matrix::identity();
+
+        // anything a command can change has to start over too, otherwise a command near the end of the script
+        // would leak into the next frame and rendering just that frame with --frame would come out different
+        self.shading_mode = DEFAULT_SHADING_MODE;
+        self.reflection_constants = DEFAULT_REFLECTION_CONSTANTS;
+        self.crease_angle = DEFAULT_CREASE_ANGLE;
+        self.culling = Culling { mode: DEFAULT_CULL_MODE, two_sided: DEFAULT_TWO_SIDED_LIGHTING };
+        self.tessellation = DEFAULT_TESSELLATION;
+        self.png_compression = DEFAULT_PNG_COMPRESSION;
+        self.passes = vec![];
+        self.outline = None;
+    }
+
+    fn save_options(&self) -> SaveOptions {
+        SaveOptions {
+            png_compression: self.png_compression,
+            passes: self.passes.clone(),
+            metadata: vec![
+                (String::from("Software"), String::from("rustic graphics engine")),
+                (String::from("Source"), self.script_path.clone()),
+                // how long the picture (or the frame in an animation) took to render
+                (String::from("Render Time"), format!("{:.3} s", self.render_start.elapsed().as_secs_f32())),
+            ],
+        }
+    }
+
+    fn get_parameter(&self, parameter: &Parameter) -> Result<f32, Box<dyn Error>> {
+        match parameter {
+            Parameter::Number(value) => Ok(*value),
+            Parameter::Knob(name) => match self.symbols.get(name) {
+                Some(Symbol::Knob(value)) => Ok(*value),
+                _ => Err(format!("Knob {} not found.", name).into()),
+            },
+            Parameter::Expression(expression) => expression.eval(&|name| match name {
+                "frame" => Some(self.frame),
+                "time" => Some(self.frame / self.fps),
+                "fps" => Some(self.fps),
+                _ => match self.symbols.get(name) {
+                    Some(Symbol::Knob(value)) => Some(*value),
+                    _ => None,
+                },
+            }),
+        }
     }
 
     fn render_edges(&mut self) {
         matrix::multiply(&self.coordinate_stack.peek(), &mut self.edges);
 
+        // every command that draws something gets its own id for the id pass, lines just face the camera for the normal pass
+        self.picture.object_id += 1;
+        self.picture.normal = [0.0, 0.0, 1.0];
         render_edges(&self.edges, &mut self.picture, &DEFAULT_FOREGROUND_COLOR);
         self.edges = matrix::new();
     }
@@ -101,55 +186,190 @@ impl ScriptContext {
         if let Some(name) = coord_system {
             if let Some(symbol) = self.symbols.get(name) {
                 match symbol {
-                    Symbol::CoordSystem(transform) => matrix::multiply(&transform, &mut self.polygons),
+                    Symbol::CoordSystem(transform) => {
+                        matrix::multiply(transform, &mut self.polygons.positions);
+                        transform_normals(transform, &mut self.polygons.normals);
+                    }
                     _ => panic!("Expected symbol to be coordinate system: {:?}", symbol)
                 }
             } else {
                 panic!("Symbol not found in table: {}", name);
             }
         } else {
-            matrix::multiply(&self.coordinate_stack.peek(), &mut self.polygons);
+            let transform = self.coordinate_stack.peek();
+            matrix::multiply(&transform, &mut self.polygons.positions);
+            transform_normals(&transform, &mut self.polygons.normals);
         }
 
-        matrix::multiply(&self.camera_matrix, &mut self.polygons);
+        matrix::multiply(&self.camera_matrix, &mut self.polygons.positions);
+        transform_normals(&self.camera_matrix, &mut self.polygons.normals);
 
-        render_polygons(&self.polygons, &mut self.picture, &DEFAULT_FOREGROUND_COLOR, &self.shading_mode, &self.lighting_config, reflection_constants);
-        self.polygons = matrix::new();
+        self.picture.opacity = reflection_constants.opacity;
+        self.picture.object_id += 1;
+        render_polygons(&self.polygons, &mut self.picture, &DEFAULT_FOREGROUND_COLOR, &self.shading_mode, &self.lighting_config, reflection_constants, self.crease_angle, &self.culling);
+
+        if let Some(outline) = &self.outline && !matches!(self.shading_mode, ShadingMode::Wireframe) {
+            render_outline(&self.polygons, &mut self.picture, outline);
+        }
+
+        self.picture.opacity = 1.0;
+        self.polygons = IndexedMesh::new();
     }
 
-    fn render_textured_polygons(&mut self, polygon_info: &Vec<(String, [[f32; 2]; 3])>, mtls: &HashMap<String, MTL>, coord_system: &Option<String>) {
+    fn render_textured_polygons(&mut self, materials: &[usize], mtls: &[MTL], coord_system: &Option<String>) {
         if let Some(name) = coord_system {
             if let Some(symbol) = self.symbols.get(name) {
                 match symbol {
-                    Symbol::CoordSystem(transform) => matrix::multiply(&transform, &mut self.polygons),
+                    Symbol::CoordSystem(transform) => {
+                        matrix::multiply(transform, &mut self.polygons.positions);
+                        transform_normals(transform, &mut self.polygons.normals);
+                    }
                     _ => panic!("Expected symbol to be coordinate system: {:?}", symbol)
                 }
             } else {
                 panic!("Symbol not found in table: {}", name);
             }
         } else {
-            matrix::multiply(&self.coordinate_stack.peek(), &mut self.polygons);
+            let transform = self.coordinate_stack.peek();
+            matrix::multiply(&transform, &mut self.polygons.positions);
+            transform_normals(&transform, &mut self.polygons.normals);
         }
 
-        matrix::multiply(&self.camera_matrix, &mut self.polygons);
+        matrix::multiply(&self.camera_matrix, &mut self.polygons.positions);
+        transform_normals(&self.camera_matrix, &mut self.polygons.normals);
         
-        let mut polygon_index = 0;
+        self.picture.object_id += 1;
 
-        for (mtl, [vt0, vt1, vt2]) in polygon_info.iter() {
-            let triangle_slice: &[[f32; 4]; 3] = self.polygons[polygon_index..polygon_index + 3].try_into().unwrap();
+        for (index, material) in materials.iter().enumerate() {
+            let normal = self.polygons.face_normal(index);
+            if self.culling.culls(&normal) {
+                continue;
+            }
+
+            let [a, b, c] = self.polygons.triangles[index];
+            let mtl = &mtls[*material];
+            self.picture.opacity = mtl.opacity;
 
             render_textured_polygon(
                 &mut self.picture,
-                triangle_slice,
-                [*vt0, *vt1, *vt2],
-                mtls.get(mtl).unwrap(),
+                &self.polygons.triangle(index),
+                [self.polygons.uvs[a], self.polygons.uvs[b], self.polygons.uvs[c]],
+                mtl,
                 &self.lighting_config.point_lights[0][1], // too lazy to do multiple point lights for textures (might do later)
+                self.culling.flips(&normal),
             );
+        }
 
-            polygon_index += 3; 
+        self.picture.opacity = 1.0;
+
+        self.polygons = IndexedMesh::new();
+    }
+
+    fn get_steps(&self, steps: Option<i32>, length: f32, coord_system: &Option<String>) -> i32 {
+        // steps given to the command itself always win over the tessellation setting
+        if let Some(steps) = steps {
+            return steps;
         }
 
-        self.polygons = matrix::new();
+        match self.tessellation {
+            Tessellation::Fixed(steps) => steps,
+            Tessellation::Adaptive(segment_length) => {
+                // length is how long the curve (or the way around the shape) is before it's transformed
+                // the camera only rotates and translates so the transformation matrix is the only thing that changes its size on screen
+                let transform = match coord_system.as_ref().and_then(|name| self.symbols.get(name)) {
+                    Some(Symbol::CoordSystem(transform)) => transform.clone(),
+                    _ => self.coordinate_stack.peek(),
+                };
+
+                // how much the matrix stretches things is the length of its longest axis
+                let scale = transform[..3].iter()
+                    .map(|axis| (axis[0] * axis[0] + axis[1] * axis[1] + axis[2] * axis[2]).sqrt())
+                    .fold(0.0, f32::max);
+
+                ((length * scale / segment_length).ceil() as i32).clamp(MIN_PARAMETRIC_STEPS, MAX_PARAMETRIC_STEPS)
+            }
+        }
+    }
+
+    fn get_outline(&self, commands: &[Command]) -> Vec<[f32; 2]> {
+        // strings the pieces of an extrude block together into one closed outline
+        // the z values of lines are ignored since the outline is flat
+        let mut outline: Vec<[f32; 2]> = vec![];
+
+        for command in commands {
+            let points = match *command {
+                Command::Line { x0, y0, x1, y1, .. } => vec![[x0, y0], [x1, y1]],
+                Command::Circle { x, y, r, steps, .. } => {
+                    circle_points(x, y, r, self.get_steps(steps, 2.0 * PI * r, &None))
+                }
+                Command::Hermite { x0, y0, x1, y1, rx0, ry0, rx1, ry1, steps } => {
+                    let curve = Curve::Hermite { x0, y0, x1, y1, rx0, ry0, rx1, ry1 };
+                    curve_points(&curve, self.get_steps(steps, curve_length(&curve), &None))
+                }
+                Command::Bezier { x0, y0, x1, y1, x2, y2, x3, y3, steps } => {
+                    let curve = Curve::Bezier { x0, y0, x1, y1, x2, y2, x3, y3 };
+                    curve_points(&curve, self.get_steps(steps, curve_length(&curve), &None))
+                }
+                _ => vec![],
+            };
+
+            // pieces usually start where the last one ended so skip points that are already there
+            for point in points {
+                if let Some(last) = outline.last() && (last[0] - point[0]).hypot(last[1] - point[1]) < 1e-4 {
+                    continue;
+                }
+                outline.push(point);
+            }
+        }
+
+        // same for the end of the outline meeting the start
+        if outline.len() > 1 && (outline[0][0] - outline[outline.len() - 1][0]).hypot(outline[0][1] - outline[outline.len() - 1][1]) < 1e-4 {
+            outline.pop();
+        }
+
+        outline
+    }
+
+    fn get_curve_points(&self, command: &Command) -> Vec<[f32; 3]> {
+        // samples any of the curve commands into points so they can be drawn or followed as a path
+        let flat = |points: Vec<[f32; 2]>, z: f32| points.into_iter().map(|[x, y]| [x, y, z]).collect();
+
+        match *command {
+            Command::Line { x0, y0, z0, x1, y1, z1 } => vec![[x0, y0, z0], [x1, y1, z1]],
+            Command::Circle { x, y, z, r, steps } => {
+                flat(circle_points(x, y, r, self.get_steps(steps, 2.0 * PI * r, &None)), z)
+            }
+            Command::Hermite { x0, y0, x1, y1, rx0, ry0, rx1, ry1, steps } => {
+                let curve = Curve::Hermite { x0, y0, x1, y1, rx0, ry0, rx1, ry1 };
+                flat(curve_points(&curve, self.get_steps(steps, curve_length(&curve), &None)), 0.0)
+            }
+            Command::Bezier { x0, y0, x1, y1, x2, y2, x3, y3, steps } => {
+                let curve = Curve::Bezier { x0, y0, x1, y1, x2, y2, x3, y3 };
+                flat(curve_points(&curve, self.get_steps(steps, curve_length(&curve), &None)), 0.0)
+            }
+            Command::Hermite3D { x0, y0, z0, x1, y1, z1, rx0, ry0, rz0, rx1, ry1, rz1, steps } => {
+                let (p0, p1, r0, r1) = ([x0, y0, z0], [x1, y1, z1], [rx0, ry0, rz0], [rx1, ry1, rz1]);
+                let length = distance(&p0, &p1) + (distance(&[0.0; 3], &r0) + distance(&[0.0; 3], &r1)) / 3.0;
+                hermite3d_points(&p0, &p1, &r0, &r1, self.get_steps(steps, length, &None))
+            }
+            Command::Bezier3D { x0, y0, z0, x1, y1, z1, x2, y2, z2, x3, y3, z3, steps } => {
+                let (p0, p1, p2, p3) = ([x0, y0, z0], [x1, y1, z1], [x2, y2, z2], [x3, y3, z3]);
+                let length = distance(&p0, &p1) + distance(&p1, &p2) + distance(&p2, &p3);
+                bezier3d_points(&p0, &p1, &p2, &p3, self.get_steps(steps, length, &None))
+            }
+            Command::CatmullRom { ref points, steps } | Command::BSpline { ref points, steps } => {
+                // steps are per segment between two control points so use the average distance between them
+                let length = points.windows(2).map(|pair| distance(&pair[0], &pair[1])).sum::<f32>() / (points.len() - 1) as f32;
+                let steps = self.get_steps(steps, length, &None);
+
+                if let Command::CatmullRom { .. } = command {
+                    catmull_rom_points(points, steps)
+                } else {
+                    b_spline_points(points, steps)
+                }
+            }
+            _ => vec![],
+        }
     }
 
     fn get_knob_value(&self, knob_name: &Option<String>) -> f32 {
@@ -174,8 +394,70 @@ impl ScriptContext {
         }
     }
 
-    fn save_coord_system(&mut self, name: String) {
-        self.symbols.insert(name, Symbol::CoordSystem(self.coordinate_stack.peek()));
+    fn save_coord_system(&mut self, name: String, joint: Option<String>) -> Result<(), Box<dyn Error>> {
+        let transform = match joint {
+            Some(joint) => self.joint_matrix(&joint)?,
+            None => self.coordinate_stack.peek(),
+        };
+
+        self.symbols.insert(name, Symbol::CoordSystem(transform));
+        Ok(())
+    }
+
+    fn define_joint(&mut self, name: String, parent: Option<String>, pivot: [f32; 3]) -> Result<(), Box<dyn Error>> {
+        // walking up from the parent should never reach this joint, otherwise the joints would be each other's parents forever
+        let mut ancestor = parent.clone();
+        while let Some(current) = ancestor {
+            if current == name {
+                return Err(format!("Joint {} can't be attached to {} since it's already above it.", name, parent.unwrap()).into());
+            }
+
+            ancestor = match self.symbols.get(&current) {
+                Some(Symbol::Joint { parent, .. }) => parent.clone(),
+                _ => return Err(format!("Joint {} not found.", current).into()),
+            };
+        }
+
+        // defining it again (like every frame of an animation) takes away its old pose
+        self.symbols.insert(name, Symbol::Joint { parent, base: self.coordinate_stack.peek(), pivot, pose: matrix::identity() });
+        Ok(())
+    }
+
+    fn pose_joint(&mut self, name: &String, rotation: Matrix) -> Result<(), Box<dyn Error>> {
+        match self.symbols.get_mut(name) {
+            Some(Symbol::Joint { pose, .. }) => {
+                *pose = transform(pose, rotation);
+                Ok(())
+            }
+            _ => Err(format!("Joint {} not found.", name).into()),
+        }
+    }
+
+    fn joint_matrix(&self, name: &String) -> Result<Matrix, Box<dyn Error>> {
+        // the same as a push, move to the pivot, and rotate for the joint and every joint above it
+        // so posing a joint rotates it around its pivot and takes everything attached below it along
+        match self.symbols.get(name) {
+            Some(Symbol::Joint { parent, base, pivot, pose }) => {
+                let parent_matrix = match parent {
+                    Some(parent) => self.joint_matrix(parent)?,
+                    None => base.clone(),
+                };
+
+                let at_pivot = transform(&parent_matrix, matrix::translation(pivot[0], pivot[1], pivot[2]));
+                Ok(transform(&at_pivot, pose.clone()))
+            }
+            _ => Err(format!("Joint {} not found.", name).into()),
+        }
+    }
+
+    fn follow_path(&mut self, name: &String, fraction: f32) -> Result<(), Box<dyn Error>> {
+        if let Some(Symbol::Path(points)) = self.symbols.get(name) {
+            let [x, y, z] = point_along_path(points, fraction);
+            self.coordinate_stack.apply_transformation(matrix::translation(x, y, z));
+            Ok(())
+        } else {
+            Err(format!("Path {} not found.", name).into())
+        }
     }
 
     fn save_composite_command(&mut self, name: String, commands: Vec<Command>) {
@@ -184,53 +466,129 @@ impl ScriptContext {
 
     fn run_composite_command(&mut self, name: String) -> Result<(), Box<dyn Error>> {
         if let Some(Symbol::CompositeCommand(commands)) = self.symbols.get(&name) {
-            evaluate_commands(self, commands.clone())
+            evaluate_commands(self, commands.clone(), FrameRange::default())
         } else {
             Err(format!("Composite command {} not found.", name).into())
         }
     } 
 }
 
-pub fn evaluate_commands(context: &mut ScriptContext, commands: Vec<Command>) -> Result<(), Box<dyn Error>> {
-    let (num_frames, basename) = animation::first_pass(&commands)?;
+pub fn evaluate_commands(context: &mut ScriptContext, commands: Vec<Command>, frame_range: FrameRange) -> Result<(), Box<dyn Error>> {
+    let AnimationSettings { num_frames, basename, fps, motion_blur, frame_range: script_frame_range } = animation::first_pass(&commands)?;
 
     if num_frames == 0 {
+        if frame_range.frames.is_some() || frame_range.every.is_some() {
+            println!("Frames were picked to render but the script isn't an animation so everything was run.");
+        }
+
         for command in commands {
             execute_command(command, context, false)?;
         }
     } else {
-        let frame_knob_list = animation::second_pass(&commands, &num_frames)?;
+        let frame_knob_list = animation::second_pass(&commands, &num_frames, fps)?;
         let mut gif = Animation::new(context.picture.xres, context.picture.yres);
 
-        for frame in 0..num_frames {
-            context.frame_reset();
+        context.fps = fps;
 
-            for (name, value) in &frame_knob_list[frame] {
-                context.set_knob(name.clone(), *value);
+        let mut cache = FrameCache::load(&basename, &commands);
+        let mut reused_frames = 0;
+
+        // the frame index stays the real one so the files line up with the full animation
+        for frame in frame_range.or(script_frame_range).selected(num_frames)? {
+            let file_path = format!("temp_frames/{}_{:03}.png", basename, frame);
+
+            // motion blur also looks at the knobs on the frames next to this one
+            let used_knobs = match motion_blur {
+                Some((samples, _)) if samples > 1 => &frame_knob_list[frame.saturating_sub(1)..=(frame + 1).min(num_frames - 1)],
+                _ => &frame_knob_list[frame..=frame],
+            };
+            let hash = cache.frame_hash(frame, used_knobs);
+
+            if GENERATE_TEMPORARY_FRAME_FILES && CACHE_TEMPORARY_FRAME_FILES && cache.is_unchanged(frame, hash, &file_path) {
+                reused_frames += 1;
+                continue;
             }
 
-            for command in commands.clone() {
-                execute_command(command, context, true)?;
+            context.render_start = Instant::now();
+
+            match motion_blur {
+                Some((samples, shutter_angle)) if samples > 1 => {
+                    // render the frame a few times spread across the time the shutter is open and average them
+                    // the first sample is exactly on the frame and the rest go part of the way towards the next one
+                    // colors are weighted by alpha so see through edges don't pick up the background color
+                    let mut total = vec![0.0; context.picture.data.len()];
+                    let mut total_alpha = vec![0.0; context.picture.alpha.len()];
+
+                    for sample in 0..samples {
+                        let offset = shutter_angle / 360.0 * sample as f32 / samples as f32;
+                        let knobs = animation::sample_knobs(&frame_knob_list, frame, offset);
+                        render_frame(context, &commands, frame as f32 + offset, &knobs)?;
+                        context.picture.flatten();
+
+                        for (i, alpha) in context.picture.alpha.iter().enumerate() {
+                            total_alpha[i] += alpha;
+
+                            for channel in 0..3 {
+                                total[i * 3 + channel] += context.picture.data[i * 3 + channel] * alpha;
+                            }
+                        }
+                    }
+
+                    for (i, alpha) in total_alpha.iter().enumerate() {
+                        // a pixel that's see through in every sample keeps the last sample's background color
+                        if *alpha > 0.0 {
+                            for channel in 0..3 {
+                                context.picture.data[i * 3 + channel] = total[i * 3 + channel] / alpha;
+                            }
+                        }
+
+                        context.picture.alpha[i] = alpha / samples as f32;
+                    }
+                }
+                _ => render_frame(context, &commands, frame as f32, &frame_knob_list[frame])?,
             }
 
             if GENERATE_TEMPORARY_FRAME_FILES {
-                context.picture.save_as_file(format!("temp_frames/{}_{:03}.png", basename, frame).as_str())?;
+                context.picture.save_as_file(&file_path, &context.save_options())?;
+
+                if CACHE_TEMPORARY_FRAME_FILES {
+                    cache.update(frame, hash, context.passes.iter().map(|pass| pass.file_path(&file_path)).collect())?;
+                }
             } else {
-                gif.add_frame(&context.picture.data);
+                gif.add_frame(&context.picture.to_rgba8());
             }
         }
 
         if !GENERATE_TEMPORARY_FRAME_FILES {
             println!("Writing gif, please wait.");
-            gif.save_as_file(format!("{}.gif", basename), DEFAULT_ANIMATION_DELAY_MS)?;
+            gif.save_as_file(format!("{}.gif", basename), fps)?;
         } else {
-            println!("Please use 'make animate B=basename' or 'make gif B=basename' in order to see the gif. Replace basename with the basename you chose.")
+            if reused_frames > 0 {
+                println!("{} frames didn't change since the last run and were kept from temp_frames.", reused_frames);
+            }
+
+            println!("Please use 'make animate B={0} FPS={1}' or 'make gif B={0} FPS={1}' in order to see the gif.", basename, fps)
         }
     }
 
     Ok(())
 }
 
+fn render_frame(context: &mut ScriptContext, commands: &[Command], frame: f32, knobs: &HashMap<String, f32>) -> Result<(), Box<dyn Error>> {
+    context.frame_reset();
+    context.frame = frame;
+
+    for (name, value) in knobs {
+        context.set_knob(name.clone(), *value);
+    }
+
+    for command in commands.iter().cloned() {
+        execute_command(command, context, true)?;
+    }
+
+    Ok(())
+}
+
 fn execute_command(command: Command, context: &mut ScriptContext, animation: bool) -> Result<(), Box<dyn Error>> {
     match command {
         Command::Display => {
@@ -241,7 +599,7 @@ fn execute_command(command: Command, context: &mut ScriptContext, animation: boo
 
         Command::Save { file_path } => {
             if !animation {
-                context.picture.save_as_file(&file_path)?
+                context.picture.save_as_file(&file_path, &context.save_options())?
             }
         }
 
@@ -267,6 +625,16 @@ fn execute_command(command: Command, context: &mut ScriptContext, animation: boo
             context.coordinate_stack.apply_transformation(matrix::dilation(a * multiplier, b * multiplier, c * multiplier));
         }
 
+        Command::FollowPath { name, t, knob } => {
+            let multiplier = context.get_knob_value(&knob);
+            context.follow_path(&name, t * multiplier)?;
+        }
+
+        Command::DefinePath { name, curve } => {
+            let points = context.get_curve_points(&curve);
+            context.symbols.insert(name, Symbol::Path(points));
+        }
+
         Command::Rotate { axis, degrees, knob } => {
             let multiplier = context.get_knob_value(&knob);
             context.coordinate_stack.apply_transformation(matrix::rotation(axis, degrees * multiplier));
@@ -277,18 +645,29 @@ fn execute_command(command: Command, context: &mut ScriptContext, animation: boo
             context.render_edges();
         }
 
-        Command::Circle { x, y, z, r } => {
-            add_circle(&mut context.edges, x, y, z, r);
+        Command::Circle { x, y, z, r, steps } => {
+            let steps = context.get_steps(steps, 2.0 * PI * r, &None);
+            add_circle(&mut context.edges, x, y, z, r, steps);
+            context.render_edges();
+        }
+
+        Command::Hermite { x0, y0, x1, y1, rx0, ry0, rx1, ry1, steps } => {
+            let length = curve_length(&Curve::Hermite { x0, y0, x1, y1, rx0, ry0, rx1, ry1 });
+            let steps = context.get_steps(steps, length, &None);
+            add_hermite_curve(&mut context.edges, [x0, y0], [x1, y1], [rx0, ry0], [rx1, ry1], steps);
             context.render_edges();
         }
 
-        Command::Hermite { x0, y0, x1, y1, rx0, ry0, rx1, ry1 } => {
-            add_hermite_curve(&mut context.edges, x0, y0, x1, y1, rx0, ry0, rx1, ry1);
+        Command::Bezier { x0, y0, x1, y1, x2, y2, x3, y3, steps } => {
+            let length = curve_length(&Curve::Bezier { x0, y0, x1, y1, x2, y2, x3, y3 });
+            let steps = context.get_steps(steps, length, &None);
+            add_bezier_curve(&mut context.edges, [x0, y0], [x1, y1], [x2, y2], [x3, y3], steps);
             context.render_edges();
         }
 
-        Command::Bezier { x0, y0, x1, y1, x2, y2, x3, y3 } => {
-            add_bezier_curve(&mut context.edges, x0, y0, x1, y1, x2, y2, x3, y3);
+        Command::Hermite3D { .. } | Command::Bezier3D { .. } | Command::CatmullRom { .. } | Command::BSpline { .. } => {
+            let points = context.get_curve_points(&command);
+            add_path(&mut context.edges, &points);
             context.render_edges();
         }
 
@@ -302,65 +681,143 @@ fn execute_command(command: Command, context: &mut ScriptContext, animation: boo
             context.render_polygons(&constants, &coord_system);
         }
 
-        Command::Sphere { constants, x, y, z, r, coord_system } => {
-            add_sphere(&mut context.polygons, x, y, z, r);
+        Command::Sphere { constants, x, y, z, r, steps, coord_system } => {
+            let steps = context.get_steps(steps, 2.0 * PI * r, &coord_system);
+            add_sphere(&mut context.polygons, x, y, z, r, steps);
+            context.render_polygons(&constants, &coord_system);
+        }
+
+        Command::Torus { constants, x, y, z, r0, r1, steps, coord_system } => {
+            let steps = context.get_steps(steps, 2.0 * PI * (r0 + r1), &coord_system);
+            add_torus(&mut context.polygons, x, y, z, r0, r1, steps);
+            context.render_polygons(&constants, &coord_system);
+        }
+
+        Command::Cylinder { constants, x, y, z, r, h, steps, coord_system } => {
+            let steps = context.get_steps(steps, 2.0 * PI * r, &coord_system);
+            add_cylinder(&mut context.polygons, x, y, z, r, h, steps);
             context.render_polygons(&constants, &coord_system);
         }
 
-        Command::Torus { constants, x, y, z, r0, r1, coord_system } => {
-            add_torus(&mut context.polygons, x, y, z, r0, r1);
+        Command::Cone { constants, x, y, z, r, h, steps, coord_system } => {
+            let steps = context.get_steps(steps, 2.0 * PI * r, &coord_system);
+            add_cone(&mut context.polygons, x, y, z, r, h, steps);
             context.render_polygons(&constants, &coord_system);
         }
 
-        Command::Cylinder { constants, x, y, z, r, h, coord_system } => {
-            add_cylinder(&mut context.polygons, x, y, z, r, h);
+        Command::Ellipsoid { constants, x, y, z, rx, ry, rz, steps, coord_system } => {
+            let steps = context.get_steps(steps, 2.0 * PI * rx.max(ry).max(rz), &coord_system);
+            add_ellipsoid(&mut context.polygons, [x, y, z], [rx, ry, rz], steps);
             context.render_polygons(&constants, &coord_system);
         }
 
-        Command::Cone { constants, x, y, z, r, h, coord_system } => {
-            add_cone(&mut context.polygons, x, y, z, r, h);
+        Command::Capsule { constants, x, y, z, r, h, steps, coord_system } => {
+            let steps = context.get_steps(steps, 2.0 * PI * r, &coord_system);
+            add_capsule(&mut context.polygons, x, y, z, r, h, steps);
+            context.render_polygons(&constants, &coord_system);
+        }
+
+        Command::Frustum { constants, x, y, z, r0, r1, h, steps, coord_system } => {
+            let steps = context.get_steps(steps, 2.0 * PI * r0.max(r1), &coord_system);
+            add_frustum(&mut context.polygons, [x, y, z], r0, r1, h, steps);
+            context.render_polygons(&constants, &coord_system);
+        }
+
+        Command::Plane { constants, x, y, z, w, d, steps, coord_system } => {
+            let steps = context.get_steps(steps, w.max(d), &coord_system);
+            add_plane(&mut context.polygons, x, y, z, w, d, steps);
+            context.render_polygons(&constants, &coord_system);
+        }
+
+        Command::Disc { constants, x, y, z, r, steps, coord_system } => {
+            let steps = context.get_steps(steps, 2.0 * PI * r, &coord_system);
+            add_disc(&mut context.polygons, x, y, z, r, 0.0, steps);
+            context.render_polygons(&constants, &coord_system);
+        }
+
+        Command::Annulus { constants, x, y, z, r0, r1, steps, coord_system } => {
+            let steps = context.get_steps(steps, 2.0 * PI * r0, &coord_system);
+            add_disc(&mut context.polygons, x, y, z, r0, r1, steps);
+            context.render_polygons(&constants, &coord_system);
+        }
+
+        Command::Prism { constants, x, y, z, r, h, sides, coord_system } => {
+            add_prism(&mut context.polygons, x, y, z, r, h, sides);
+            context.render_polygons(&constants, &coord_system);
+        }
+
+        Command::Pyramid { constants, x, y, z, r, h, sides, coord_system } => {
+            add_pyramid(&mut context.polygons, x, y, z, r, h, sides);
+            context.render_polygons(&constants, &coord_system);
+        }
+
+        Command::Icosphere { constants, x, y, z, r, subdivisions, coord_system } => {
+            add_icosphere(&mut context.polygons, x, y, z, r, subdivisions);
+            context.render_polygons(&constants, &coord_system);
+        }
+
+        Command::Lathe { constants, axis, curve, steps, coord_system } => {
+            let profile = curve_points(&curve, context.get_steps(None, curve_length(&curve), &coord_system));
+
+            // the steps given here are for going around the axis, so base them on the widest part of the profile
+            let radius = profile.iter().map(|[x, y]| match axis {
+                matrix::Rotation::X => y.abs(),
+                _ => x.abs(),
+            }).fold(0.0, f32::max);
+            let steps = context.get_steps(steps, 2.0 * PI * radius, &coord_system);
+
+            add_lathe(&mut context.polygons, &profile, axis, steps);
+            context.render_polygons(&constants, &coord_system);
+        }
+
+        Command::Extrude { constants, path, steps, outline, coord_system } => {
+            let outline = context.get_outline(&outline);
+            if outline.len() < 3 {
+                return Err(format!("Extrude outline needs at least 3 different points but only has {}.", outline.len()).into());
+            }
+
+            let path = match path {
+                ExtrudePath::Depth(depth) => vec![[0.0, 0.0, 0.0], [0.0, 0.0, -depth]],
+                ExtrudePath::Curve(curve) => {
+                    let steps = context.get_steps(steps, curve_length(&curve), &coord_system);
+                    curve_points(&curve, steps).iter().map(|[x, y]| [*x, *y, 0.0]).collect()
+                }
+            };
+
+            add_sweep(&mut context.polygons, &outline, &path);
             context.render_polygons(&constants, &coord_system);
         }
 
         Command::Mesh { constants, file_path, coord_system } => {
-            let mut polygons: Matrix = vec![];
-            let mut polygon_info: Vec<(String, [[f32; 2]; 3])> = vec![];
-            let mut mtls: HashMap<String, MTL> = HashMap::new();
             if let Some(cache) = context.mesh_cache.get(&file_path) {
                 match cache {
-                    CachedMesh::NoTexture(cache) => polygons = cache.clone(), 
-                    CachedMesh::Texture(cache) => {
-                        polygons = cache.0.clone();
-                        polygon_info = cache.1.clone();
-                        mtls = cache.2.clone();
+                    CachedMesh::NoTexture(mesh) => {
+                        context.polygons = mesh.clone();
+                        context.render_polygons(&constants, &coord_system);
+                    }
+                    CachedMesh::Texture((mesh, materials, mtls)) => {
+                        let (materials, mtls) = (materials.clone(), mtls.clone());
+                        context.polygons = mesh.clone();
+                        context.render_textured_polygons(&materials, &mtls, &coord_system);
                     }
                 }
-            }
-
-            if !polygons.is_empty() {
-                context.polygons = polygons.clone(); 
-                if !polygon_info.is_empty() {
-                    context.render_textured_polygons(&polygon_info, &mtls, &coord_system);
-                } else {
-                    context.render_polygons(&constants, &coord_system);
-                }
-            } else if let Some((polygon_info, mtls)) = handle_mesh(&mut context.polygons, &file_path)? {
-                polygons = context.polygons.clone();
-                context.render_textured_polygons(&polygon_info, &mtls, &coord_system);
+            } else if let Some((materials, mtls)) = handle_mesh(&mut context.polygons, &file_path)? {
+                let mesh = context.polygons.clone();
+                context.render_textured_polygons(&materials, &mtls, &coord_system);
                 context.mesh_cache.insert(
                     file_path,
                     CachedMesh::Texture((
-                        polygons,
-                        polygon_info,
+                        mesh,
+                        materials,
                         mtls,
                     ))
                 );
             } else {
-                polygons = context.polygons.clone();
+                let mesh = context.polygons.clone();
                 context.render_polygons(&constants, &coord_system);
                 context.mesh_cache.insert(
                     file_path,
-                    CachedMesh::NoTexture(polygons)
+                    CachedMesh::NoTexture(mesh)
                 );
             }
         }
@@ -370,32 +827,164 @@ fn execute_command(command: Command, context: &mut ScriptContext, animation: boo
         }
 
         Command::AddLight { r, g, b, x, y, z } => {
-            context.lighting_config.point_lights.push([[r, g, b], normalize_vector(&[x, y, z])]);
+            let color = [context.get_parameter(&r)?, context.get_parameter(&g)?, context.get_parameter(&b)?];
+            let direction = [context.get_parameter(&x)?, context.get_parameter(&y)?, context.get_parameter(&z)?];
+            context.lighting_config.point_lights.push([color, normalize_vector(&direction)]);
         }
 
         Command::SetAmbient { r, g, b } => {
-            context.lighting_config.ambient_light_color = [r, g, b];
+            context.lighting_config.ambient_light_color = [context.get_parameter(&r)?, context.get_parameter(&g)?, context.get_parameter(&b)?];
         }
 
-        Command::DefineConstants { name, kar, kdr, ksr, kag, kdg, ksg, kab, kdb, ksb } => {
+        Command::DefineConstants { name, kar, kdr, ksr, kag, kdg, ksg, kab, kdb, ksb, opacity } => {
             let constants = ReflectionConstants {
-                ambient: [kar, kag, kab],
-                diffuse: [kdr, kdg, kdb],
-                specular: [ksr, ksg, ksb],
+                ambient: [context.get_parameter(&kar)?, context.get_parameter(&kag)?, context.get_parameter(&kab)?],
+                diffuse: [context.get_parameter(&kdr)?, context.get_parameter(&kdg)?, context.get_parameter(&kdb)?],
+                specular: [context.get_parameter(&ksr)?, context.get_parameter(&ksg)?, context.get_parameter(&ksb)?],
+                opacity: match &opacity {
+                    Some(opacity) => context.get_parameter(opacity)?.clamp(0.0, 1.0),
+                    None => 1.0,
+                },
             };
 
             context.symbols.insert(name, Symbol::Constants(constants));
         }
 
+        Command::SetPngCompression { compression } => {
+            context.png_compression = compression;
+        }
+
+        // these go straight on the picture since it's made again every frame and the commands run again anyway
+        Command::SetToneMapping { tone_mapping } => {
+            context.picture.tone_mapping = tone_mapping;
+        }
+
+        Command::SetExposure { stops } => {
+            context.picture.exposure = context.get_parameter(&stops)?;
+        }
+
+        Command::SetGamma { srgb } => {
+            context.picture.srgb = srgb;
+        }
+
+        Command::SetOutline { width, color } => {
+            context.outline = match width {
+                Some(width) => {
+                    let color = match color {
+                        Some((r, g, b)) => [context.get_parameter(&r)?, context.get_parameter(&g)?, context.get_parameter(&b)?],
+                        None => [0.0; 3],
+                    };
+
+                    // keeps the crease setting from before
+                    let crease_angle = context.outline.and_then(|outline| outline.crease_angle);
+                    Some(Outline { width: context.get_parameter(&width)?, color, crease_angle })
+                }
+                None => None,
+            };
+        }
+
+        Command::SetOutlineCreases { degrees } => {
+            match &mut context.outline {
+                Some(outline) => outline.crease_angle = degrees,
+                None => return Err("outline_creases needs an outline to be turned on first.".into()),
+            }
+        }
+
+        Command::SetCulling { mode } => {
+            context.culling.mode = mode;
+        }
+
+        Command::SetTwoSidedLighting { enabled } => {
+            context.culling.two_sided = enabled;
+        }
+
+        Command::SetDepthTest { enabled } => {
+            context.picture.z_test = enabled;
+        }
+
+        Command::SetDepthWrite { enabled } => {
+            context.picture.z_write = enabled;
+        }
+
+        Command::SetDepthCompare { compare } => {
+            context.picture.z_compare = compare;
+        }
+
+        Command::SetPasses { passes } => {
+            context.passes = passes;
+        }
+
+        Command::Blur { radius } => {
+            let radius = context.get_parameter(&radius)?;
+            post_process::blur(&mut context.picture, radius);
+        }
+
+        Command::Bloom { threshold, strength, radius } => {
+            let (threshold, strength, radius) = (context.get_parameter(&threshold)?, context.get_parameter(&strength)?, context.get_parameter(&radius)?);
+            post_process::bloom(&mut context.picture, threshold, strength, radius);
+        }
+
+        Command::Vignette { strength } => {
+            let strength = context.get_parameter(&strength)?;
+            post_process::vignette(&mut context.picture, strength);
+        }
+
+        Command::Grade { brightness, contrast, saturation } => {
+            let (brightness, contrast, saturation) = (context.get_parameter(&brightness)?, context.get_parameter(&contrast)?, context.get_parameter(&saturation)?);
+            post_process::grade(&mut context.picture, brightness, contrast, saturation);
+        }
+
+        Command::Lut { file_path } => {
+            let lut = post_process::load_lut(&file_path)?;
+            post_process::apply_lut(&mut context.picture, &lut);
+        }
+
+        Command::DepthOfField { focus, range, radius } => {
+            let (focus, range, radius) = (context.get_parameter(&focus)?, context.get_parameter(&range)?, context.get_parameter(&radius)?);
+            post_process::depth_of_field(&mut context.picture, focus, range, radius);
+        }
+
+        Command::EdgeDetect { threshold, color } => {
+            let color = match color {
+                Some((r, g, b)) => [context.get_parameter(&r)?, context.get_parameter(&g)?, context.get_parameter(&b)?],
+                None => [0.0; 3],
+            };
+
+            let threshold = context.get_parameter(&threshold)?;
+            post_process::edges(&mut context.picture, threshold, &color);
+        }
+
+        Command::SetBackground { color } => {
+            match color {
+                Some((r, g, b)) => {
+                    let color = [context.get_parameter(&r)?, context.get_parameter(&g)?, context.get_parameter(&b)?];
+                    context.picture.set_background(&color, 1.0);
+                }
+                None => {
+                    // keeps the default color for formats that don't have alpha
+                    let (r, g, b) = DEFAULT_BACKGROUND_COLOR;
+                    context.picture.set_background(&[r as f32, g as f32, b as f32], 0.0);
+                }
+            }
+        }
+
         Command::SetShading { shading_mode } => {
-            context.shading_mode = shading_mode.clone();
+            context.shading_mode = shading_mode;
+        }
+
+        Command::SetTessellation { tessellation } => {
+            context.tessellation = tessellation;
+        }
+
+        Command::SetCreaseAngle { degrees } => {
+            context.crease_angle = degrees;
         }
 
         Command::SetCamera { eye_x, eye_y, eye_z, aim_x, aim_y, aim_z } => {
             // based on opengl's camera transformation matrix
             // keeps the viewing vector for the math at a consistent <0, 0, 1>
-            let eye = [eye_x, eye_y, eye_z];
-            let aim = [aim_x, aim_y, aim_z];
+            let eye = [context.get_parameter(&eye_x)?, context.get_parameter(&eye_y)?, context.get_parameter(&eye_z)?];
+            let aim = [context.get_parameter(&aim_x)?, context.get_parameter(&aim_y)?, context.get_parameter(&aim_z)?];
             let forward = normalize_vector(&subtract_vectors(&aim, &eye));
             let up = [0.0, 1.0, 0.0];
 
@@ -422,8 +1011,17 @@ fn execute_command(command: Command, context: &mut ScriptContext, animation: boo
             context.set_all_knobs(value);
         }
 
-        Command::SaveCoordSystem { name } => {
-            context.save_coord_system(name);
+        Command::SaveCoordSystem { name, joint } => {
+            context.save_coord_system(name, joint)?;
+        }
+
+        Command::DefineJoint { name, parent, x, y, z } => {
+            context.define_joint(name, parent, [x, y, z])?;
+        }
+
+        Command::PoseJoint { name, axis, degrees, knob } => {
+            let multiplier = context.get_knob_value(&knob);
+            context.pose_joint(&name, matrix::rotation(axis, degrees * multiplier))?;
         }
         
         Command::CreateComposite { name, commands } => {
@@ -440,3 +1038,72 @@ fn execute_command(command: Command, context: &mut ScriptContext, animation: boo
     Ok(())
 }
 
+fn transform(matrix: &Matrix, transformation: Matrix) -> Matrix {
+    // applies a transformation the same way the coordinate stack does
+    let mut result = transformation;
+    matrix::multiply(matrix, &mut result);
+    result
+}
+
+fn transform_normals(transformation: &[[f32; 4]], normals: &mut Matrix) {
+    matrix::multiply(&matrix::normal_matrix(transformation), normals);
+
+    // the inverse transpose keeps the direction right but not the length
+    for normal in normals.iter_mut() {
+        let length = (normal[0] * normal[0] + normal[1] * normal[1] + normal[2] * normal[2]).sqrt();
+        if length > 0.0 {
+            *normal = [normal[0] / length, normal[1] / length, normal[2] / length, 0.0];
+        }
+    }
+}
+
+fn curve_length(curve: &Curve) -> f32 {
+    // a rough upper bound for how long the curve is, only used for adaptive tessellation
+    match *curve {
+        Curve::Hermite { x0, y0, x1, y1, rx0, ry0, rx1, ry1 } => {
+            // the same curve as a bezier would have its inner control points a third of the way along each rate of change
+            (x1 - x0).hypot(y1 - y0) + (rx0.hypot(ry0) + rx1.hypot(ry1)) / 3.0
+        }
+        Curve::Bezier { x0, y0, x1, y1, x2, y2, x3, y3 } => {
+            // a bezier curve is never longer than the lines between its control points
+            (x1 - x0).hypot(y1 - y0) + (x2 - x1).hypot(y2 - y1) + (x3 - x2).hypot(y3 - y2)
+        }
+    }
+}
+
+fn curve_points(curve: &Curve, steps: i32) -> Vec<[f32; 2]> {
+    match *curve {
+        Curve::Hermite { x0, y0, x1, y1, rx0, ry0, rx1, ry1 } => hermite_points([x0, y0], [x1, y1], [rx0, ry0], [rx1, ry1], steps),
+        Curve::Bezier { x0, y0, x1, y1, x2, y2, x3, y3 } => bezier_points([x0, y0], [x1, y1], [x2, y2], [x3, y3], steps),
+    }
+}
+
+fn distance(a: &[f32; 3], b: &[f32; 3]) -> f32 {
+    let difference = subtract_vectors(b, a);
+    dot_product(&difference, &difference).sqrt()
+}
+
+fn point_along_path(points: &[[f32; 3]], fraction: f32) -> [f32; 3] {
+    // fraction is how far along the path to go by distance (not by t) so things following it move at a steady speed
+    // anything outside of 0 to 1 just stays at the ends
+    let lengths: Vec<f32> = points.windows(2).map(|pair| distance(&pair[0], &pair[1])).collect();
+    let mut remaining = lengths.iter().sum::<f32>() * fraction.clamp(0.0, 1.0);
+
+    for (i, length) in lengths.iter().enumerate() {
+        if remaining <= *length && *length > 0.0 {
+            let t = remaining / length;
+            let (a, b) = (points[i], points[i + 1]);
+            return [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t, a[2] + (b[2] - a[2]) * t];
+        }
+        remaining -= length;
+    }
+
+    points.last().copied().unwrap_or([0.0; 3])
+}
+
+fn default_lighting_config() -> LightingConfig {
+    LightingConfig {
+        ambient_light_color: [50.0, 50.0, 50.0],
+        point_lights: vec![[[255.0, 255.0, 255.0], normalize_vector(&[0.5, 0.75, 1.0])]],
+    }
+}
@@ -10,9 +10,11 @@ pub enum TokenType {
     Command(Function),
     AxisOfRotation,
     Number,
+    Time,
     FilePath,
     Identifier,
     EasingFunction,
+    Symbol,
     Begin,
     End,
 }
@@ -24,6 +26,7 @@ pub enum Function {
     Save,
     Clear,
     SetCamera,
+    SetTessellation,
     CreateComposite,
     RunComposite,
 
@@ -34,12 +37,19 @@ pub enum Function {
     Scale,
     Rotate,
     SaveCoordSystem,
+    FollowPath,
+    DefineJoint,
+    PoseJoint,
 
     // EDGES
     Line,
     Circle,
     Hermite,
     Bezier,
+    Hermite3D,
+    Bezier3D,
+    CatmullRom,
+    BSpline,
 
     // POLYGONS
     Polygon,
@@ -48,6 +58,17 @@ pub enum Function {
     Torus,
     Cylinder,
     Cone,
+    Ellipsoid,
+    Capsule,
+    Frustum,
+    Plane,
+    Disc,
+    Annulus,
+    Prism,
+    Pyramid,
+    Icosphere,
+    Lathe,
+    Extrude,
     Mesh,
 
     // LIGHTING
@@ -56,15 +77,53 @@ pub enum Function {
     SetAmbient,
     DefineConstants,
     SetShading,
+    SetPngCompression,
+    SetToneMapping,
+    SetExposure,
+    SetGamma,
+    SetBackground,
+    SetPasses,
+    SetOutline,
+    SetOutlineCreases,
+    SetCulling,
+    SetTwoSidedLighting,
+    SetDepthTest,
+    SetDepthWrite,
+    SetDepthCompare,
+    SetCreaseAngle,
 
     // ANIMATION
     SetBaseName,
     SetKnob,
     SaveKnobList,
+    BlendKnobs,
+    Layer,
     Tween,
     SetFrames,
+    SetFrameRate,
+    SetDuration,
+    SetMotionBlur,
+    RenderFrames,
+    RenderFrame,
+    RenderEvery,
     VaryKnob,
+    Keyframes,
+    Wiggle,
+    Oscillate,
+    LoopKnob,
+    PingPongKnob,
+    KnobExpression,
     SetAllKnobs,
+    DefinePath,
+
+    // POST PROCESSING
+    Blur,
+    Bloom,
+    Vignette,
+    Grade,
+    Lut,
+    DepthOfField,
+    EdgeDetect,
 
     // UNIMPLEMENTED BUT RECOGNIZED
     GenerateRayFiles,
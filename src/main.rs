@@ -6,18 +6,60 @@ mod interpreter;
 mod vector;
 
 use std::{error::Error, env};
+use interpreter::FrameRange;
+
 #[show_image::main]
 fn main() -> Result<(), Box<dyn Error>> {
-    let arguments: Vec<String> = env::args().collect();
+    let (paths, frame_range) = parse_arguments(env::args().skip(1).collect())?;
 
-    if arguments.len() < 2 {
+    if paths.is_empty() {
         println!("A path to a script wasn't provided. '{}' was chosen by default.", &constants::DEFAULT_SCRIPT);
     } else {
-        for path in &arguments[1..] {
+        for path in &paths {
             println!("Running script '{}'.", path);
-            interpreter::run_script(path)?;
+            interpreter::run_script(path, frame_range)?;
         }
     }
 
     Ok(())
 }
+
+fn parse_arguments(arguments: Vec<String>) -> Result<(Vec<String>, FrameRange), Box<dyn Error>> {
+    // --frames 100-150, --frame 137, and --every 5 only render some of the frames of an animation
+    let mut paths = vec![];
+    let mut frame_range = FrameRange::default();
+    let mut arguments = arguments.into_iter();
+
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--frames" | "--frame" | "--every" => {
+                let value = arguments.next().ok_or(format!("{} needs a value after it.", argument))?;
+                let parse = |text: &str| text.trim().parse::<usize>().map_err(|_| format!("Error parsing frame for {}: {}", argument, value));
+
+                match argument.as_str() {
+                    "--frames" => {
+                        let (start, end) = value.split_once('-').ok_or(format!("--frames expects a range like 100-150 but received: {}", value))?;
+                        frame_range.frames = Some((parse(start)?, parse(end)?));
+                    }
+                    "--frame" => {
+                        let frame = parse(&value)?;
+                        frame_range.frames = Some((frame, frame));
+                    }
+                    _ => {
+                        let every = parse(&value)?;
+
+                        if every == 0 {
+                            return Err("--every needs a number of frames above 0.".into());
+                        }
+
+                        frame_range.every = Some(every);
+                    }
+                }
+            }
+            _ if argument.starts_with("--") => return Err(format!("Unknown option: {}", argument).into()),
+            _ => paths.push(argument),
+        }
+    }
+
+    Ok((paths, frame_range))
+}
@@ -4,6 +4,8 @@ type Matrix = Vec<[f32; 4]>;
 
 use std::f32::consts::PI;
 
+use crate::vector::{cross_product, dot_product};
+
 #[derive(Clone, Copy, Debug)]
 pub enum Rotation {
     X,
@@ -43,6 +45,29 @@ pub fn multiply(m1: &[[f32; 4]], m2: &mut Matrix) {
     *m2 = data;
 }
 
+pub fn normal_matrix(m: &[[f32; 4]]) -> Matrix {
+    // normals have to go through the inverse transpose of the top left 3x3 instead of the matrix itself
+    // otherwise a non uniform scale leaves them no longer perpendicular to the surface
+    // the inverse transpose is the matrix of cofactors divided by the determinant, and the cofactor rows are cross products of the rows
+    let row = |r: usize| [m[0][r], m[1][r], m[2][r]];
+    let [r0, r1, r2] = [row(0), row(1), row(2)];
+    let cofactors = [cross_product(&r1, &r2), cross_product(&r2, &r0), cross_product(&r0, &r1)];
+
+    let determinant = dot_product(&r0, &cofactors[0]);
+    let scale = if determinant == 0.0 { 1.0 } else { 1.0 / determinant };
+
+    let mut transformation_matrix = identity();
+
+    // stored by columns like everything else here
+    for (r, cofactor) in cofactors.iter().enumerate() {
+        for c in 0..3 {
+            transformation_matrix[c][r] = cofactor[c] * scale;
+        }
+    }
+
+    transformation_matrix
+}
+
 pub fn print(m1: &Matrix) {
     for i in 0..4 {
         for point in m1 {
@@ -1,65 +1,289 @@
 use std::{
+    cmp::Ordering,
     fs::File,
-    io::Write,
+    io::{BufWriter, Write},
     error::Error,
     path::Path,
 };
 
-use crate::constants::ENABLE_Z_BUFFER;
-use image::{ImageBuffer, Rgb};
+use crate::constants::{DEFAULT_PNG_COMPRESSION, DEFAULT_Z_COMPARE, DEFAULT_Z_TEST, DEFAULT_Z_WRITE};
+use image::{ImageBuffer, Rgb, Rgba};
 use show_image::{create_window, ImageInfo, ImageView, WindowOptions};
 
+// things that only some formats use when saving
+pub struct SaveOptions {
+    pub png_compression: png::Compression,
+    // saved as text chunks in pngs (ex: ("Source", "scripts/robot.mdl"))
+    pub metadata: Vec<(String, String)>,
+    // extra pictures saved next to the main one (ex: render.png also makes render.depth.png)
+    pub passes: Vec<Pass>,
+}
+
+impl Default for SaveOptions {
+    fn default() -> Self {
+        Self { png_compression: DEFAULT_PNG_COMPRESSION, metadata: vec![], passes: vec![] }
+    }
+}
+
+// pictures made from what's under each pixel instead of its color, for compositing and debugging
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pass {
+    Depth, // white is the closest thing in the picture and black is the farthest (or nothing)
+    Normal, // the direction the surface faces as rgb, x is red, y is green, and z (towards the camera) is blue
+    Id, // every shape command gets its own color
+}
+
+impl Pass {
+    fn name(&self) -> &str {
+        match self {
+            Pass::Depth => "depth",
+            Pass::Normal => "normal",
+            Pass::Id => "id",
+        }
+    }
+
+    // the passes go in the same format with the pass name before the extension
+    pub fn file_path(&self, filename: &str) -> String {
+        let path = Path::new(filename);
+        let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("").to_ascii_lowercase();
+
+        format!("{}.{}.{}", path.with_extension("").display(), self.name(), extension)
+    }
+}
+
+// how colors brighter than white get squished back down when the picture is turned into 8 bit colors
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToneMapping {
+    None, // anything past white is just clipped
+    Reinhard,
+    Aces,
+    Filmic,
+}
+
+// which depths pass the depth test compared to what's already in the z buffer (bigger z is closer to the camera)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DepthCompare {
+    Closer,
+    CloserOrEqual,
+    Farther,
+    FartherOrEqual,
+    Equal, // the exact same depth, for drawing the same shape over itself
+}
+
+impl DepthCompare {
+    pub fn passes(&self, z: f32, buffer: f32) -> bool {
+        match self {
+            DepthCompare::Closer => z > buffer,
+            DepthCompare::CloserOrEqual => z >= buffer,
+            DepthCompare::Farther => z < buffer,
+            DepthCompare::FartherOrEqual => z <= buffer,
+            DepthCompare::Equal => z == buffer,
+        }
+    }
+}
+
+// a see through pixel waiting to be blended on top of whatever ends up behind it
+#[derive(Debug, Clone, Copy)]
+struct Fragment {
+    z: f32,
+    color: [f32; 3],
+    opacity: f32,
+    z_test: Option<DepthCompare>, // fragments drawn without the depth test can't get covered up either
+}
+
 pub struct Picture {
     pub xres: usize,
     pub yres: usize,
     max_color: usize,
-    pub data: Vec<u8>, // flat rgb buffer that stores as [r, g, b, r, g, b, ...]
-    default_color: (usize, usize, usize),
-    z_buffer: Vec<Vec<f32>>,
+    pub data: Vec<f32>, // flat linear rgb buffer that stores as [r, g, b, r, g, b, ...] where 1.0 is white (but it can go past that)
+    pub alpha: Vec<f32>, // one per pixel, 0 is completely see through
+    default_color: [f32; 3],
+    default_alpha: f32,
+    z_buffer: Vec<f32>, // one per pixel in the same order as alpha
+    // every pixel keeps the see through things drawn on it so they can be sorted back to front at the end
+    // that way it doesn't matter what order shapes are drawn in
+    fragments: Vec<Vec<Fragment>>,
+    pub opacity: f32, // used for everything plotted until it's changed again
+    // turning these off is for overlays (no test) and decals (no write), same deal as opacity
+    pub z_test: bool,
+    pub z_write: bool,
+    pub z_compare: DepthCompare,
+    // what the passes need, only kept for solid pixels
+    normals: Vec<[f32; 3]>,
+    ids: Vec<u32>,
+    pub normal: [f32; 3], // also used for everything plotted until it's changed again
+    pub object_id: u32, // 0 is the background
+    pub exposure: f32, // in stops so every +1 doubles the brightness
+    pub tone_mapping: ToneMapping,
+    pub srgb: bool, // whether the sRGB gamma curve is applied when turning it into 8 bit colors
 }
 
 impl Picture {
     pub fn new(xres: usize, yres: usize, max_color: usize, default_color: &(usize, usize, usize)) -> Self {
-        let default_color = default_color.clone();
-        let mut data = vec![0; xres * yres * 3];
-        for y in 0..yres {
-            for x in 0..xres {
-                let i = (y * xres + x) * 3;
-                data[i] = default_color.0 as u8;
-                data[i + 1] = default_color.1 as u8;
-                data[i + 2] = default_color.2 as u8;
-            }
-        }
+        let default_color = [
+            default_color.0 as f32 / 255.0,
+            default_color.1 as f32 / 255.0,
+            default_color.2 as f32 / 255.0,
+        ];
+        let data = default_color.repeat(xres * yres);
 
-        let z_buffer = vec![vec![f32::NEG_INFINITY; xres]; yres];
+        let z_buffer = vec![f32::NEG_INFINITY; xres * yres];
 
         Self {
             xres,
             yres,
             max_color,
             data,
+            alpha: vec![1.0; xres * yres],
             default_color,
+            default_alpha: 1.0,
             z_buffer,
+            fragments: vec![vec![]; xres * yres],
+            opacity: 1.0,
+            z_test: DEFAULT_Z_TEST,
+            z_write: DEFAULT_Z_WRITE,
+            z_compare: DEFAULT_Z_COMPARE,
+            normals: vec![[0.0; 3]; xres * yres],
+            ids: vec![0; xres * yres],
+            normal: [0.0, 0.0, 1.0],
+            object_id: 0,
+            exposure: 0.0,
+            tone_mapping: ToneMapping::None,
+            srgb: false,
         }
     }
 
     pub fn clear(&mut self) {
-        for y in 0..self.yres {
-            for x in 0..self.xres {
-                let i = (y * self.xres + x) * 3;
-                self.data[i] = self.default_color.0 as u8;
-                self.data[i + 1] = self.default_color.1 as u8;
-                self.data[i + 2] = self.default_color.2 as u8;
+        self.data = self.default_color.repeat(self.xres * self.yres);
+        self.alpha = vec![self.default_alpha; self.xres * self.yres];
+
+        self.z_buffer = vec![f32::NEG_INFINITY; self.xres * self.yres];
+        self.fragments = vec![vec![]; self.xres * self.yres];
+        self.normals = vec![[0.0; 3]; self.xres * self.yres];
+        self.ids = vec![0; self.xres * self.yres];
+    }
+
+    pub fn set_background(&mut self, color: &[f32; 3], alpha: f32) {
+        // only changes the pixels nothing has been drawn on yet so it always ends up behind everything
+        self.default_color = color.map(|value| value / 255.0);
+        self.default_alpha = alpha;
+
+        for i in 0..self.xres * self.yres {
+            if self.z_buffer[i] != f32::NEG_INFINITY {
+                continue;
+            }
+
+            self.data[i * 3..i * 3 + 3].copy_from_slice(&self.default_color);
+            self.alpha[i] = alpha;
+        }
+    }
+
+    pub fn composited(&self) -> (Vec<f32>, Vec<f32>) {
+        // the colors and alpha with the see through fragments blended in, farthest first
+        let mut data = self.data.clone();
+        let mut alpha = self.alpha.clone();
+
+        for (i, fragments) in self.fragments.iter().enumerate() {
+            if fragments.is_empty() {
+                continue;
+            }
+
+            // anything solid drawn after a fragment could still have covered it up
+            let depth = self.z_buffer[i];
+            let mut visible: Vec<&Fragment> = fragments.iter()
+                .filter(|fragment| fragment.z_test.is_none_or(|compare| compare.passes(fragment.z, depth)))
+                .collect();
+
+            // the ones drawn without the depth test go on top in the order they were drawn
+            visible.sort_by(|a, b| match (a.z_test, b.z_test) {
+                (Some(_), Some(_)) => a.z.total_cmp(&b.z),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            });
+
+            for fragment in visible {
+                // the over operator with colors that aren't premultiplied
+                let behind = alpha[i] * (1.0 - fragment.opacity);
+                let total = fragment.opacity + behind;
+
+                if total > 0.0 {
+                    for channel in 0..3 {
+                        let below = data[i * 3 + channel];
+                        data[i * 3 + channel] = (fragment.color[channel] * fragment.opacity + below * behind) / total;
+                    }
+                }
+
+                alpha[i] = total;
             }
         }
 
-        self.z_buffer = vec![vec![f32::NEG_INFINITY; self.xres]; self.yres];
+        (data, alpha)
+    }
+
+    pub fn flatten(&mut self) {
+        // bakes the fragments into the picture, for when it's going to be averaged with other pictures
+        (self.data, self.alpha) = self.composited();
+        self.fragments = vec![vec![]; self.xres * self.yres];
+    }
+
+    pub fn depth(&self, x: usize, y: usize) -> Option<f32> {
+        // x and y are in the same order as data (row 0 is the top), None if nothing solid was drawn there
+        let z = self.z_buffer[y * self.xres + x];
+        (z != f32::NEG_INFINITY).then_some(z)
+    }
+
+    pub fn surface_normal(&self, x: usize, y: usize) -> [f32; 3] {
+        self.normals[y * self.xres + x]
+    }
+
+    pub fn is_transparent(&self) -> bool {
+        self.alpha.iter().any(|alpha| *alpha < 1.0) || self.fragments.iter().any(|fragments| !fragments.is_empty())
+    }
+
+    pub fn to_rgb8(&self) -> Vec<u8> {
+        // exposure, then the tone curve, then gamma, which is what every 8 bit format and the preview uses
+        // with the defaults this is just the same colors that were plotted
+        // formats without alpha just get the colors, so a transparent background shows up as the background color
+        let (data, _) = self.composited();
+
+        data.iter().map(|value| self.map_tone(*value)).collect()
+    }
+
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        let (data, alpha) = self.composited();
+
+        data.chunks(3).zip(alpha).flat_map(|(pixel, alpha)| {
+            [self.map_tone(pixel[0]), self.map_tone(pixel[1]), self.map_tone(pixel[2]), (alpha.clamp(0.0, 1.0) * 255.0).round() as u8]
+        }).collect()
+    }
+
+    fn map_tone(&self, value: f32) -> u8 {
+        let scale = 2f32.powf(self.exposure);
+        let mut value = (value * scale).max(0.0);
+
+        value = match self.tone_mapping {
+            ToneMapping::None => value,
+            // https://www-old.cs.utah.edu/docs/techreports/2002/pdf/UUCS-02-001.pdf
+            ToneMapping::Reinhard => value / (1.0 + value),
+            // krzysztof narkowicz's fit of the aces curve
+            ToneMapping::Aces => (value * (2.51 * value + 0.03)) / (value * (2.43 * value + 0.59) + 0.14),
+            // john hable's uncharted 2 curve with a white point of 11.2
+            ToneMapping::Filmic => hable(value * 2.0) / hable(11.2),
+        };
+
+        if self.srgb {
+            value = if value <= 0.0031308 { value * 12.92 } else { 1.055 * value.powf(1.0 / 2.4) - 0.055 };
+        }
+
+        (value.clamp(0.0, 1.0) * 255.0).round() as u8
     }
 
     pub fn display(&self) -> Result<(), Box<dyn Error>> {
+        let data = self.to_rgb8();
         let image = ImageView::new(
             ImageInfo::rgb8(self.xres as u32, self.yres as u32),
-            &self.data,
+            &data,
         );
 
         let window = create_window("Preview", WindowOptions {
@@ -73,7 +297,7 @@ impl Picture {
         Ok(())
     }
 
-    pub fn save_as_file(&self, filename: &str) -> Result<(), Box<dyn Error>> {
+    pub fn save_as_file(&self, filename: &str, options: &SaveOptions) -> Result<(), Box<dyn Error>> {
         let extension = Path::new(filename)
             .extension()
             .and_then(|s| s.to_str())
@@ -81,44 +305,258 @@ impl Picture {
             .to_ascii_lowercase();
 
         match extension.as_str() {
-            "ppm" => {
-                self.save_ppm(filename)?;
-                println!("{} created.", filename);
-                Ok(())
+            "ppm" => self.save_ppm(filename)?,
+            "pgm" => self.save_pgm(filename)?,
+            "png" => self.save_png(filename, options)?,
+            "bmp" | "gif" | "tga" => self.save_image(filename)?,
+            "qoi" => self.save_qoi(filename)?,
+            "pfm" => self.save_pfm(filename)?,
+            "hdr" => self.save_hdr(filename)?,
+            "" => return Err("Cannot save file: please provide a file extension.".into()),
+            _ => return Err(format!("Cannot save file: .{} not supported.", extension).into()),
+        }
+
+        println!("{} created.", filename);
+
+        for pass in &options.passes {
+            self.pass(*pass).save_as_file(&pass.file_path(filename), &SaveOptions { passes: vec![], ..SaveOptions::default() })?;
+        }
+
+        Ok(())
+    }
+
+    pub fn pass(&self, pass: Pass) -> Picture {
+        // pixels nothing was drawn on are transparent so they can't be mixed up with black
+        let mut picture = Picture::new(self.xres, self.yres, self.max_color, &(0, 0, 0));
+        picture.set_background(&[0.0; 3], 0.0);
+
+        let pixels: Vec<usize> = (0..self.xres * self.yres).filter(|i| self.z_buffer[*i] != f32::NEG_INFINITY).collect();
+
+        // depth is stretched so the closest and farthest things in this picture use the whole range
+        let depths = pixels.iter().map(|i| self.z_buffer[*i]);
+        let near = depths.clone().fold(f32::NEG_INFINITY, f32::max);
+        let far = depths.fold(f32::INFINITY, f32::min);
+
+        for i in pixels {
+            let color = match pass {
+                Pass::Depth => {
+                    let z = self.z_buffer[i];
+                    [if near > far { (z - far) / (near - far) } else { 1.0 }; 3]
+                }
+                Pass::Normal => {
+                    let [x, y, z] = self.normals[i];
+                    let length = (x * x + y * y + z * z).sqrt().max(f32::EPSILON);
+                    [x, y, z].map(|value| value / length * 0.5 + 0.5)
+                }
+                Pass::Id => id_color(self.ids[i]),
+            };
+
+            picture.data[i * 3..i * 3 + 3].copy_from_slice(&color);
+            picture.alpha[i] = 1.0;
+        }
+
+        picture
+    }
+
+    fn save_ppm(&self, filename: &str) -> Result<(), Box<dyn Error>> {
+        // binary ppm, the header is the same as the ascii one but the pixels are just the raw bytes
+        let mut file = BufWriter::new(File::create(filename)?);
+        writeln!(file, "P6 {} {} {}", self.xres, self.yres, self.max_color)?;
+        file.write_all(&self.to_rgb8())?;
+
+        Ok(())
+    }
+
+    fn save_pgm(&self, filename: &str) -> Result<(), Box<dyn Error>> {
+        // grayscale version of the binary ppm, each pixel is how bright it looks
+        let mut file = BufWriter::new(File::create(filename)?);
+        writeln!(file, "P5 {} {} {}", self.xres, self.yres, self.max_color)?;
+
+        let gray: Vec<u8> = self.to_rgb8().chunks(3)
+            .map(|pixel| (0.299 * pixel[0] as f32 + 0.587 * pixel[1] as f32 + 0.114 * pixel[2] as f32).round() as u8)
+            .collect();
+        file.write_all(&gray)?;
+
+        Ok(())
+    }
+
+    fn save_png(&self, filename: &str, options: &SaveOptions) -> Result<(), Box<dyn Error>> {
+        // the png crate directly instead of through image so the compression and text chunks can be set
+        let file = BufWriter::new(File::create(filename)?);
+        let mut encoder = png::Encoder::new(file, self.xres as u32, self.yres as u32);
+        let transparent = self.is_transparent();
+        encoder.set_color(if transparent { png::ColorType::Rgba } else { png::ColorType::Rgb });
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_compression(options.png_compression);
+
+        for (keyword, text) in &options.metadata {
+            encoder.add_text_chunk(keyword.clone(), text.clone())?;
+        }
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&if transparent { self.to_rgba8() } else { self.to_rgb8() })?;
+
+        Ok(())
+    }
+
+    fn save_image(&self, filename: &str) -> Result<(), Box<dyn Error>> {
+        // image picks the format from the extension, all three of these can keep alpha
+        if self.is_transparent() {
+            let buffer: ImageBuffer<Rgba<u8>, Vec<u8>> = ImageBuffer::from_raw(self.xres as u32, self.yres as u32, self.to_rgba8())
+                .ok_or("Failed to create image buffer")?;
+
+            buffer.save(filename)?;
+        } else {
+            let buffer: ImageBuffer<Rgb<u8>, Vec<u8>> = ImageBuffer::from_raw(self.xres as u32, self.yres as u32, self.to_rgb8())
+                .ok_or("Failed to create image buffer")?;
+
+            buffer.save(filename)?;
+        }
+
+        Ok(())
+    }
+
+    fn save_qoi(&self, filename: &str) -> Result<(), Box<dyn Error>> {
+        // https://qoiformat.org/qoi-specification.pdf
+        // every pixel is written as the smallest of: a run of the last pixel, an index into the last 64 colors seen,
+        // a small difference from the last pixel, or the full color
+        let mut bytes: Vec<u8> = b"qoif".to_vec();
+        bytes.extend((self.xres as u32).to_be_bytes());
+        bytes.extend((self.yres as u32).to_be_bytes());
+        // the channel count is only a hint for decoders, the pixels are encoded the same way either way
+        bytes.extend([if self.is_transparent() { 4 } else { 3 }, 0]); // rgb or rgba, srgb
+
+        // the decoder starts with every color here as 0 including alpha
+        let mut seen = [[0u8; 4]; 64];
+        let mut previous = [0, 0, 0, 255];
+        let mut run = 0;
+        let pixels: Vec<[u8; 4]> = self.to_rgba8().chunks(4).map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]]).collect();
+
+        for (i, pixel) in pixels.iter().enumerate() {
+            if *pixel == previous {
+                run += 1;
+
+                if run == 62 || i == pixels.len() - 1 {
+                    bytes.push(0xC0 | (run - 1));
+                    run = 0;
+                }
+
+                continue;
+            }
+
+            if run > 0 {
+                bytes.push(0xC0 | (run - 1));
+                run = 0;
             }
-            "png" => {
-                self.save_png(filename)?;
-                println!("{} created.", filename);
-                Ok(())
+
+            let [r, g, b, a] = *pixel;
+            let hash = (r as usize * 3 + g as usize * 5 + b as usize * 7 + a as usize * 11) % 64;
+
+            if seen[hash] == *pixel {
+                bytes.push(hash as u8);
+            } else if a != previous[3] {
+                // the difference ones can't change alpha
+                seen[hash] = *pixel;
+                bytes.extend([0xFF, r, g, b, a]);
+            } else {
+                seen[hash] = *pixel;
+
+                let dr = r.wrapping_sub(previous[0]) as i8;
+                let dg = g.wrapping_sub(previous[1]) as i8;
+                let db = b.wrapping_sub(previous[2]) as i8;
+                let dr_dg = dr.wrapping_sub(dg);
+                let db_dg = db.wrapping_sub(dg);
+
+                if (-2..=1).contains(&dr) && (-2..=1).contains(&dg) && (-2..=1).contains(&db) {
+                    bytes.push(0x40 | ((dr + 2) as u8) << 4 | ((dg + 2) as u8) << 2 | (db + 2) as u8);
+                } else if (-32..=31).contains(&dg) && (-8..=7).contains(&dr_dg) && (-8..=7).contains(&db_dg) {
+                    bytes.push(0x80 | (dg + 32) as u8);
+                    bytes.push(((dr_dg + 8) as u8) << 4 | (db_dg + 8) as u8);
+                } else {
+                    bytes.extend([0xFE, r, g, b]);
+                }
             }
-            "" => Err("Cannot save file: please provide a file extension.".into()),
-            _ => Err(format!("Cannot save file: .{} not supported.", extension).into()),
+
+            previous = *pixel;
         }
+
+        bytes.extend([0, 0, 0, 0, 0, 0, 0, 1]);
+        std::fs::write(filename, bytes)?;
+
+        Ok(())
     }
 
-    fn save_ppm(&self, filename: &str) -> Result<(), Box<dyn Error>> {
-        let mut file = File::create(filename)?;
-        writeln!(file, "P3 {} {} {}", self.xres, self.yres, self.max_color)?;
+    fn save_pfm(&self, filename: &str) -> Result<(), Box<dyn Error>> {
+        // the raw buffer before exposure or tone mapping, a negative scale means little endian
+        // pfm goes from the bottom row up so the rows are flipped
+        let mut file = BufWriter::new(File::create(filename)?);
+        write!(file, "PF\n{} {}\n-1.0\n", self.xres, self.yres)?;
 
-        for y in 0..self.yres {
-            for x in 0..self.xres {
-                let i = (y * self.xres + x) * 3;
-                writeln!(file, "{} {} {}", self.data[i], self.data[i+1], self.data[i+2])?;
+        let (data, _) = self.composited();
+        for row in data.chunks(self.xres * 3).rev() {
+            for value in row {
+                file.write_all(&value.to_le_bytes())?;
             }
         }
 
         Ok(())
     }
 
-    fn save_png(&self, filename: &str) -> Result<(), Box<dyn Error>> {
-        let buffer: ImageBuffer<Rgb<u8>, Vec<u8>> = ImageBuffer::from_raw(self.xres as u32, self.yres as u32, self.data.clone())
-            .ok_or("Failed to create image buffer")?;
+    fn save_hdr(&self, filename: &str) -> Result<(), Box<dyn Error>> {
+        // radiance rgbe, also the raw buffer
+        // every pixel shares one exponent between its three colors, which is plenty for how bright things get
+        let mut file = BufWriter::new(File::create(filename)?);
+        write!(file, "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n", self.yres, self.xres)?;
+
+        let (data, _) = self.composited();
+        for row in data.chunks(self.xres * 3) {
+            let pixels: Vec<[u8; 4]> = row.chunks(3).map(|pixel| rgbe(pixel[0], pixel[1], pixel[2])).collect();
+
+            // the run length version only works for rows this wide, otherwise it's just the pixels
+            if !(8..32768).contains(&self.xres) {
+                for pixel in pixels {
+                    file.write_all(&pixel)?;
+                }
+                continue;
+            }
+
+            file.write_all(&[2, 2, (self.xres >> 8) as u8, (self.xres & 255) as u8])?;
+
+            // each channel is written separately as runs of the same byte or chunks of different ones
+            for channel in 0..4 {
+                let values: Vec<u8> = pixels.iter().map(|pixel| pixel[channel]).collect();
+                let mut i = 0;
+
+                while i < values.len() {
+                    let run = values[i..].iter().take(127).take_while(|value| **value == values[i]).count();
+
+                    if run >= 3 {
+                        file.write_all(&[128 + run as u8, values[i]])?;
+                        i += run;
+                        continue;
+                    }
+
+                    // a chunk goes until the next run of at least 3
+                    let mut end = i + 1;
+                    while end < values.len() && end - i < 128 {
+                        if end + 2 < values.len() && values[end] == values[end + 1] && values[end] == values[end + 2] {
+                            break;
+                        }
+                        end += 1;
+                    }
+
+                    file.write_all(&[(end - i) as u8])?;
+                    file.write_all(&values[i..end])?;
+                    i = end;
+                }
+            }
+        }
 
-        buffer.save(filename)?;
         Ok(())
     }
 
-    pub fn plot(&mut self, x: isize, y: isize, z: f32, color: &(usize, usize, usize)) {
+    pub fn plot(&mut self, x: isize, y: isize, z: f32, color: &[f32; 3]) {
+        // colors are in the same units as everything else so 255 is white, but it can go higher now
         if x < 0 || y < 0 || x >= self.xres as isize || y >= self.yres as isize {
             return;
         }
@@ -126,23 +564,32 @@ impl Picture {
         let x = x as usize;
         let y = y as usize;
         let y = (self.yres - 1) - y;
+        let i = y * self.xres + x;
 
-        let z_truncated = (z * 10000.0) as isize;
-        let buffer_truncated = (self.z_buffer[y][x] * 10000.0) as isize;
+        if self.z_test && !self.z_compare.passes(z, self.z_buffer[i]) {
+            return;
+        }
 
-        if ENABLE_Z_BUFFER && z_truncated < buffer_truncated {
+        let color = color.map(|value| value / 255.0);
+
+        // see through things don't go in the z buffer so whatever's behind them still gets drawn
+        if self.opacity < 1.0 {
+            self.fragments[i].push(Fragment { z, color, opacity: self.opacity, z_test: self.z_test.then_some(self.z_compare) });
             return;
         }
 
-        let i = (y * self.xres + x) * 3;
-        self.data[i] = color.0 as u8;
-        self.data[i + 1] = color.1 as u8;
-        self.data[i + 2] = color.2 as u8;
+        self.data[i * 3..i * 3 + 3].copy_from_slice(&color);
+        self.alpha[i] = 1.0;
+        self.normals[i] = self.normal;
+        self.ids[i] = self.object_id;
 
-        self.z_buffer[y][x] = z;
+        if self.z_write {
+            self.z_buffer[i] = z;
+        }
     }
 
     pub fn draw_line(&mut self, mut x0: isize, mut y0: isize, mut z0: f32, x1: isize, y1: isize, z1: f32, color: &(usize, usize, usize)) {
+        let color = [color.0 as f32, color.1 as f32, color.2 as f32];
         let dx = (x1 - x0).abs();
         let dy = (y1 - y0).abs();
         let step_x = if x1 > x0 { 1 } else { -1 };
@@ -183,4 +630,67 @@ impl Picture {
 
         self.plot(x0, y0, z0, &color);
     }
+
+    pub fn draw_wide_line(&mut self, start: [f32; 3], end: [f32; 3], width: f32, color: &[f32; 3]) {
+        // a circle of pixels stamped all along the line, a width of 1 or less is just a normal line
+        let [x0, y0, z0] = start;
+        let [x1, y1, z1] = end;
+        let radius = (width / 2.0).max(0.5);
+        let reach = radius.ceil() as isize;
+        let steps = (x1 - x0).abs().max((y1 - y0).abs()).ceil().max(1.0) as usize;
+
+        for step in 0..=steps {
+            let t = step as f32 / steps as f32;
+            let x = (x0 + (x1 - x0) * t).round() as isize;
+            let y = (y0 + (y1 - y0) * t).round() as isize;
+            let z = z0 + (z1 - z0) * t;
+
+            for dy in -reach..=reach {
+                for dx in -reach..=reach {
+                    if ((dx * dx + dy * dy) as f32) <= radius * radius {
+                        self.plot(x + dx, y + dy, z, color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn hable(x: f32) -> f32 {
+    let (a, b, c, d, e, f) = (0.15, 0.5, 0.1, 0.2, 0.02, 0.3);
+    ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f
+}
+
+fn rgbe(r: f32, g: f32, b: f32) -> [u8; 4] {
+    // the mantissas are scaled so the brightest color fills a byte
+    let brightest = r.max(g).max(b);
+
+    if brightest < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+
+    let exponent = brightest.log2().floor() as i32 + 1;
+    let scale = 256.0 / 2f32.powi(exponent);
+
+    [
+        (r.max(0.0) * scale) as u8,
+        (g.max(0.0) * scale) as u8,
+        (b.max(0.0) * scale) as u8,
+        (exponent + 128) as u8,
+    ]
+}
+
+fn id_color(id: u32) -> [f32; 3] {
+    // stepping the hue by the golden ratio keeps ids next to each other from getting similar colors
+    let hue = (id as f32 * 0.618034).fract() * 6.0;
+    let x = 1.0 - (hue % 2.0 - 1.0).abs();
+
+    match hue as usize {
+        0 => [1.0, x, 0.0],
+        1 => [x, 1.0, 0.0],
+        2 => [0.0, 1.0, x],
+        3 => [0.0, x, 1.0],
+        4 => [x, 0.0, 1.0],
+        _ => [1.0, 0.0, x],
+    }
 }
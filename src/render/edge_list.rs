@@ -1,12 +1,13 @@
 use std::f32::consts::PI;
 
 use crate::{
-    constants::{PARAMETRIC_STEPS, HERMITE, BEZIER},
+    constants::{HERMITE, BEZIER, CATMULL_ROM, B_SPLINE},
     matrix::add_point,
 };
 use super::Picture;
 
 type EdgeList = Vec<[f32; 4]>;
+type Vector = [f32; 3];
 
 pub fn add_edge(m: &mut EdgeList, x0: f32, y0: f32, z0: f32, x1: f32, y1: f32, z1: f32) {
     add_point(m, x0, y0, z0, 1.0);
@@ -20,59 +21,133 @@ pub fn render_edges(m: &EdgeList, picture: &mut Picture, color: &(usize, usize,
     }
 }
 
-fn run_parametric<X, Y>(m: &mut EdgeList, x: X, y: Y, z: Option<f32>)
+fn sample_parametric<X, Y>(x: X, y: Y, steps: i32) -> Vec<[f32; 2]>
     where X: Fn(f32) -> f32, Y: Fn(f32) -> f32, {
     // we can use parametric equations for things like circles and splines
     // t = 0
     // t -> 1
     // x and y have their own functions of t
-    let z_val = z.unwrap_or(0.0);
-
-    // we need to store this so we can draw edges between consecutive points
-    let mut last_point = (x(0.0), y(0.0), z_val);
-
-    // t -> 1
-    for i in 0..=PARAMETRIC_STEPS {
-        let t = i as f32 / PARAMETRIC_STEPS as f32;
-        let current_point = (x(t), y(t), z_val);
-
-        add_edge(
-            m,
-            last_point.0, last_point.1, last_point.2,
-            current_point.0, current_point.1, current_point.2,
-        );
+    (0..=steps).map(|i| {
+        let t = i as f32 / steps as f32;
+        [x(t), y(t)]
+    }).collect()
+}
 
-        last_point = current_point;
+pub fn add_path(m: &mut EdgeList, points: &[Vector]) {
+    // draw edges between consecutive points
+    for pair in points.windows(2) {
+        add_edge(m, pair[0][0], pair[0][1], pair[0][2], pair[1][0], pair[1][1], pair[1][2]);
     }
 }
 
-pub fn add_circle(m: &mut EdgeList, cx: f32, cy: f32, cz: f32, r: f32) {
+fn flat_path(points: &[[f32; 2]], z: f32) -> Vec<Vector> {
+    points.iter().map(|[x, y]| [*x, *y, z]).collect()
+}
+
+pub fn circle_points(cx: f32, cy: f32, r: f32, steps: i32) -> Vec<[f32; 2]> {
     // x(t) = rcos(2 * pi * t) + cx
     // y(t) = rsin(2 * pi * t) + cy
     let x = |t: f32| r * (2.0 * PI * t).cos() + cx;
     let y = |t: f32| r * (2.0 * PI * t).sin() + cy;
 
-    run_parametric(m, x, y, Some(cz));
+    sample_parametric(x, y, steps)
 }
 
-pub fn add_hermite_curve(m: &mut EdgeList, x0: f32, y0: f32, x1: f32, y1: f32, rx0: f32, ry0: f32, rx1: f32, ry1: f32) {
+pub fn hermite_points(p0: [f32; 2], p1: [f32; 2], r0: [f32; 2], r1: [f32; 2], steps: i32) -> Vec<[f32; 2]> {
+    // p0 and p1 are the endpoints and r0 and r1 are the rates of change at each end
     // find coefficients for for at^3 + bt^2 + ct + d
+    let ([x0, y0], [x1, y1], [rx0, ry0], [rx1, ry1]) = (p0, p1, r0, r1);
     let mut g = vec![[x0, x1, rx0, rx1], [y0, y1, ry0, ry1]];
     crate::matrix::multiply(&HERMITE, &mut g);
 
     let x = |t: f32| t * (t * (t * g[0][0] + g[0][1]) + g[0][2]) + g[0][3];
     let y = |t: f32| t * (t * (t * g[1][0] + g[1][1]) + g[1][2]) + g[1][3];
 
-    run_parametric(m, x, y, None);
+    sample_parametric(x, y, steps)
 }
 
-pub fn add_bezier_curve(m: &mut EdgeList, x0: f32, y0: f32, x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32) {
+pub fn bezier_points(p0: [f32; 2], p1: [f32; 2], p2: [f32; 2], p3: [f32; 2], steps: i32) -> Vec<[f32; 2]> {
     // find coefficients for for at^3 + bt^2 + ct + d
+    let ([x0, y0], [x1, y1], [x2, y2], [x3, y3]) = (p0, p1, p2, p3);
     let mut g = vec![[x0, x1, x2, x3], [y0, y1, y2, y3]];
     crate::matrix::multiply(&BEZIER, &mut g);
 
     let x = |t: f32| t * (t * (t * g[0][0] + g[0][1]) + g[0][2]) + g[0][3];
     let y = |t: f32| t * (t * (t * g[1][0] + g[1][1]) + g[1][2]) + g[1][3];
 
-    run_parametric(m, x, y, None);
+    sample_parametric(x, y, steps)
+}
+
+fn sample_cubic(basis: &[[f32; 4]; 4], geometry: [&Vector; 4], steps: i32) -> Vec<Vector> {
+    // same idea as the 2d curves but with z as well
+    // the basis matrix turns the four geometry points into the coefficients for at^3 + bt^2 + ct + d on each axis
+    let mut g = (0..3).map(|axis| geometry.map(|point| point[axis])).collect();
+    crate::matrix::multiply(basis, &mut g);
+
+    (0..=steps).map(|i| {
+        let t = i as f32 / steps as f32;
+        [0, 1, 2].map(|axis| t * (t * (t * g[axis][0] + g[axis][1]) + g[axis][2]) + g[axis][3])
+    }).collect()
+}
+
+pub fn hermite3d_points(p0: &Vector, p1: &Vector, r0: &Vector, r1: &Vector, steps: i32) -> Vec<Vector> {
+    sample_cubic(&HERMITE, [p0, p1, r0, r1], steps)
+}
+
+pub fn bezier3d_points(p0: &Vector, p1: &Vector, p2: &Vector, p3: &Vector, steps: i32) -> Vec<Vector> {
+    sample_cubic(&BEZIER, [p0, p1, p2, p3], steps)
+}
+
+fn sample_segments(basis: &[[f32; 4]; 4], points: &[Vector], steps: i32) -> Vec<Vector> {
+    // every window of 4 control points is its own cubic segment with steps pieces
+    // the last point of a segment is the first point of the next one so it only gets added once
+    let mut result: Vec<Vector> = vec![];
+
+    for window in points.windows(4) {
+        let segment = sample_cubic(basis, [&window[0], &window[1], &window[2], &window[3]], steps);
+        let skip = if result.is_empty() { 0 } else { 1 };
+        result.extend(segment.into_iter().skip(skip));
+    }
+
+    result
+}
+
+pub fn catmull_rom_points(points: &[Vector], steps: i32) -> Vec<Vector> {
+    // goes through every point, each segment only uses the points on either side of it to figure out its direction
+    // the first and last points get repeated so the curve reaches them too
+    if points.len() < 2 {
+        return points.to_vec();
+    }
+
+    let mut padded = vec![points[0]];
+    padded.extend_from_slice(points);
+    padded.push(points[points.len() - 1]);
+
+    sample_segments(&CATMULL_ROM, &padded, steps)
+}
+
+pub fn b_spline_points(points: &[Vector], steps: i32) -> Vec<Vector> {
+    // smoother than catmull rom but it only gets pulled towards the points instead of going through them
+    // repeating the ends 3 times makes it start and end exactly on the first and last points
+    if points.len() < 2 {
+        return points.to_vec();
+    }
+
+    let mut padded = vec![points[0]; 2];
+    padded.extend_from_slice(points);
+    padded.extend([points[points.len() - 1]; 2]);
+
+    sample_segments(&B_SPLINE, &padded, steps)
+}
+
+pub fn add_circle(m: &mut EdgeList, cx: f32, cy: f32, cz: f32, r: f32, steps: i32) {
+    add_path(m, &flat_path(&circle_points(cx, cy, r, steps), cz));
+}
+
+pub fn add_hermite_curve(m: &mut EdgeList, p0: [f32; 2], p1: [f32; 2], r0: [f32; 2], r1: [f32; 2], steps: i32) {
+    add_path(m, &flat_path(&hermite_points(p0, p1, r0, r1, steps), 0.0));
+}
+
+pub fn add_bezier_curve(m: &mut EdgeList, p0: [f32; 2], p1: [f32; 2], p2: [f32; 2], p3: [f32; 2], steps: i32) {
+    add_path(m, &flat_path(&bezier_points(p0, p1, p2, p3, steps), 0.0));
 }
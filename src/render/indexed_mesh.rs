@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use crate::vector::{add_vectors, cross_product, dot_product, normalize_vector};
+
+type Vector = [f32; 3];
+
+// an indexed triangle mesh
+// every vertex is stored once and triangles refer to them by index so neighbouring faces actually share corners
+// positions (and normals) are kept as [x, y, z, w] so the transformation matrices can be applied to them directly
+// normals use w = 0 so they get rotated and scaled but never translated
+// the attribute lists are either empty (not provided) or exactly as long as positions
+#[derive(Clone, Debug, Default)]
+pub struct IndexedMesh {
+    pub positions: Vec<[f32; 4]>,
+    pub normals: Vec<[f32; 4]>,
+    pub uvs: Vec<[f32; 2]>,
+    pub colors: Vec<Vector>,
+    // which point each vertex really is, for meshes that split a point into several vertices just for different uvs or colors
+    // (an obj corner with a new vt but the same v), smoothing goes by these so the split doesn't turn into a hard edge
+    pub position_ids: Vec<usize>,
+    pub triangles: Vec<[usize; 3]>,
+}
+
+impl IndexedMesh {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_vertex(&mut self, x: f32, y: f32, z: f32) -> usize {
+        self.positions.push([x, y, z, 1.0]);
+        self.positions.len() - 1
+    }
+
+    pub fn add_triangle(&mut self, a: usize, b: usize, c: usize) {
+        self.triangles.push([a, b, c]);
+    }
+
+    pub fn has_normals(&self) -> bool {
+        !self.normals.is_empty() && self.normals.len() == self.positions.len()
+    }
+
+    pub fn has_uvs(&self) -> bool {
+        !self.uvs.is_empty() && self.uvs.len() == self.positions.len()
+    }
+
+    pub fn has_colors(&self) -> bool {
+        !self.colors.is_empty() && self.colors.len() == self.positions.len()
+    }
+
+    pub fn has_position_ids(&self) -> bool {
+        !self.position_ids.is_empty() && self.position_ids.len() == self.positions.len()
+    }
+
+    pub fn triangle(&self, index: usize) -> [[f32; 4]; 3] {
+        let [a, b, c] = self.triangles[index];
+        [self.positions[a], self.positions[b], self.positions[c]]
+    }
+
+    pub fn face_normal(&self, index: usize) -> Vector {
+        let [p0, p1, p2] = self.triangle(index);
+
+        let a = [p1[0] - p0[0], p1[1] - p0[1], p1[2] - p0[2]];
+        let b = [p2[0] - p0[0], p2[1] - p0[1], p2[2] - p0[2]];
+
+        // not normalized on purpose, the length is twice the area of the triangle
+        // so bigger faces end up pulling the smooth normals harder
+        cross_product(&a, &b)
+    }
+
+    pub fn corner_colors(&self, index: usize) -> [Vector; 3] {
+        if !self.has_colors() {
+            return [[1.0, 1.0, 1.0]; 3];
+        }
+
+        let [a, b, c] = self.triangles[index];
+        [self.colors[a], self.colors[b], self.colors[c]]
+    }
+
+    pub fn weld(&mut self) {
+        // merges vertices that are exactly the same (position and every attribute we have)
+        // unlike rounding to integers this never merges two points that are merely close to each other
+        // so seams with different normals or uvs stay split
+        let has_normals = self.has_normals();
+        let has_uvs = self.has_uvs();
+        let has_colors = self.has_colors();
+        let has_position_ids = self.has_position_ids();
+
+        let mut seen: HashMap<Vec<u32>, usize> = HashMap::new();
+        let mut remap: Vec<usize> = Vec::with_capacity(self.positions.len());
+        let mut welded = IndexedMesh::new();
+
+        for i in 0..self.positions.len() {
+            let mut key: Vec<u32> = self.positions[i][..3].iter().map(|v| float_key(*v)).collect();
+            if has_normals { key.extend(self.normals[i][..3].iter().map(|v| float_key(*v))); }
+            if has_uvs { key.extend(self.uvs[i].iter().map(|v| float_key(*v))); }
+            if has_colors { key.extend(self.colors[i].iter().map(|v| float_key(*v))); }
+
+            let index = *seen.entry(key).or_insert_with(|| {
+                welded.positions.push(self.positions[i]);
+                if has_normals { welded.normals.push(self.normals[i]); }
+                if has_uvs { welded.uvs.push(self.uvs[i]); }
+                if has_colors { welded.colors.push(self.colors[i]); }
+                if has_position_ids { welded.position_ids.push(self.position_ids[i]); }
+                welded.positions.len() - 1
+            });
+
+            remap.push(index);
+        }
+
+        welded.triangles = self.triangles.iter().map(|[a, b, c]| [remap[*a], remap[*b], remap[*c]]).collect();
+
+        *self = welded;
+    }
+
+    pub fn corner_normals(&self, crease_angle: f32) -> Vec<[Vector; 3]> {
+        // one normal per corner of every triangle
+        // if the mesh came with its own normals we just use those
+        if self.has_normals() {
+            return self.triangles.iter().map(|[a, b, c]| [
+                safe_normalize(&self.normals[*a]),
+                safe_normalize(&self.normals[*b]),
+                safe_normalize(&self.normals[*c]),
+            ]).collect();
+        }
+
+        let face_normals: Vec<Vector> = (0..self.triangles.len()).map(|i| self.face_normal(i)).collect();
+        let unit_normals: Vec<Vector> = face_normals.iter().map(|n| safe_normalize(&[n[0], n[1], n[2], 0.0])).collect();
+
+        // which faces touch each point
+        // vertices that are only split for their uvs or colors count as the same point
+        let point = |vertex: usize| if self.has_position_ids() { self.position_ids[vertex] } else { vertex };
+        let mut point_faces: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (face, triangle) in self.triangles.iter().enumerate() {
+            for vertex in triangle {
+                point_faces.entry(point(*vertex)).or_default().push(face);
+            }
+        }
+
+        // a neighbouring face only gets averaged in if the angle between it and the current face is within the crease angle
+        // anything sharper is treated as a hard edge so the corner keeps its own normal on each side
+        let threshold = crease_angle.to_radians().cos();
+
+        self.triangles.iter().enumerate().map(|(face, triangle)| {
+            triangle.map(|vertex| {
+                let mut sum = [0.0, 0.0, 0.0];
+
+                for neighbour in &point_faces[&point(vertex)] {
+                    if crease_angle >= 180.0 || dot_product(&unit_normals[face], &unit_normals[*neighbour]) >= threshold {
+                        sum = add_vectors(&sum, &face_normals[*neighbour]);
+                    }
+                }
+
+                if dot_product(&sum, &sum) == 0.0 {
+                    unit_normals[face]
+                } else {
+                    normalize_vector(&sum)
+                }
+            })
+        }).collect()
+    }
+}
+
+fn float_key(value: f32) -> u32 {
+    // adding 0.0 turns -0.0 into 0.0 so they hash the same
+    (value + 0.0).to_bits()
+}
+
+fn safe_normalize(vector: &[f32; 4]) -> Vector {
+    let vector = [vector[0], vector[1], vector[2]];
+
+    if dot_product(&vector, &vector) == 0.0 {
+        vector
+    } else {
+        normalize_vector(&vector)
+    }
+}
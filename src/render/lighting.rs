@@ -16,20 +16,22 @@ pub struct ReflectionConstants {
     pub ambient: Vector,
     pub diffuse: Vector,
     pub specular: Vector,
+    pub opacity: f32, // 1 is solid, anything less gets blended with what's behind it
 }
 
-pub fn get_illumination(normal: &Vector, config: &LightingConfig, constants: &ReflectionConstants) -> (usize, usize, usize) {
+pub fn get_illumination(normal: &Vector, config: &LightingConfig, constants: &ReflectionConstants) -> Vector {
+    // not clamped so bright spots and lots of lights can go past 255, the picture tone maps it when it's saved
     let normal = &normalize_vector(&normal);
 
     let ambient = get_ambient(&config.ambient_light_color, &constants.ambient);
     let diffuse = get_diffuse(normal, &config.point_lights, &constants.diffuse);
     let specular = get_specular(normal, &config.point_lights, &constants.specular);
 
-    clamp_color([
+    [
         ambient[0] + diffuse[0] + specular[0],
         ambient[1] + diffuse[1] + specular[1],
         ambient[2] + diffuse[2] + specular[2],
-    ])
+    ]
 }
 
 pub fn get_ambient(ambient_light_color: &Vector, ambient_constant: &Vector) -> Vector {
@@ -72,10 +74,25 @@ pub fn get_specular(normal: &Vector, point_lights: &Vec<[Vector; 2]>, specular_c
     specular
 }
 
-fn clamp_color(vector: Vector) -> (usize, usize, usize) {
-    (
-        vector[0].clamp(0.0, 255.0) as usize,
-        vector[1].clamp(0.0, 255.0) as usize,
-        vector[2].clamp(0.0, 255.0) as usize,
-    )
+pub fn quantize(color: Vector, bands: usize) -> Vector {
+    // for toon shading the brightness snaps up to the next of a few evenly spaced levels
+    // the color is scaled as a whole so it keeps its hue
+    let brightness = (0.2126 * color[0] + 0.7152 * color[1] + 0.0722 * color[2]) / 255.0;
+
+    if brightness <= 0.0 {
+        return color;
+    }
+
+    let level = (brightness * bands as f32).ceil() / bands as f32;
+    color.map(|value| value * level / brightness)
+}
+
+pub fn apply_vertex_color(color: Vector, vertex_color: &Vector) -> Vector {
+    // vertex colors are 0 to 1 and just scale whatever the lighting came up with
+    // meshes without colors use white so this doesn't change anything for them
+    [
+        color[0] * vertex_color[0],
+        color[1] * vertex_color[1],
+        color[2] * vertex_color[2],
+    ]
 }
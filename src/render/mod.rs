@@ -2,7 +2,9 @@ pub mod lighting;
 pub mod scan_line;
 pub mod edge_list;
 pub mod polygon_list;
+pub mod indexed_mesh;
 pub mod texture;
+pub mod post_process;
 
-pub use crate::picture::Picture;
+pub use crate::picture::{DepthCompare, Pass, Picture, SaveOptions, ToneMapping};
 pub use lighting::{LightingConfig, ReflectionConstants, get_illumination};
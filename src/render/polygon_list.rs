@@ -1,6 +1,6 @@
 use rand::Rng;
 
-type PolygonList = Vec<[f32; 4]>;
+type PolygonList = IndexedMesh;
 type Vector = [f32; 3];
 
 use std::{
@@ -9,87 +9,89 @@ use std::{
 };
 
 use crate::{
-    constants::{CUBE, ENABLE_BACK_FACE_CULLING, PARAMETRIC_STEPS, ShadingMode},
-    matrix::add_point,
-    vector::{add_vectors, cross_product, normalize_vector}
+    constants::{CUBE, ICOSAHEDRON, SWEEP_CORNER_ANGLE, UV_CHECKER_SQUARES, CullMode, ShadingMode},
+    matrix::Rotation,
+    vector::{cross_product, dot_product, normalize_vector, subtract_vectors},
 };
 use super::{
     scan_line,
-    Picture, LightingConfig, ReflectionConstants, get_illumination,
+    indexed_mesh::IndexedMesh,
+    Picture, LightingConfig, ReflectionConstants, get_illumination, lighting::apply_vertex_color,
 };
 
-fn vector_to_key(vector: &[f32; 4]) -> (isize, isize, isize) {
-    (vector[0].round() as isize, vector[1].round() as isize, vector[2].round() as isize)
+pub fn add_polygon(m: &mut PolygonList, x0: f32, y0: f32, z0: f32, x1: f32, y1: f32, z1: f32, x2: f32, y2: f32, z2: f32) {
+    let a = m.add_vertex(x0, y0, z0);
+    let b = m.add_vertex(x1, y1, z1);
+    let c = m.add_vertex(x2, y2, z2);
+    m.add_triangle(a, b, c);
 }
 
-pub fn add_polygon(m: &mut PolygonList, x0: f32, y0: f32, z0: f32, x1: f32, y1: f32, z1: f32, x2: f32, y2: f32, z2: f32) {
-    add_point(m, x0, y0, z0, 1.0);
-    add_point(m, x1, y1, z1, 1.0);
-    add_point(m, x2, y2, z2, 1.0);
+// lines drawn around shapes, mostly for toon shading
+#[derive(Debug, Clone, Copy)]
+pub struct Outline {
+    pub width: f32,
+    pub color: [f32; 3],
+    pub crease_angle: Option<f32>, // also outline the edges where faces meet sharper than this (in degrees)
+}
+
+// which faces get skipped, and whether the faces pointing away get lit from their other side when they're drawn
+#[derive(Debug, Clone, Copy)]
+pub struct Culling {
+    pub mode: CullMode,
+    pub two_sided: bool,
+}
+
+impl Culling {
+    pub fn culls(&self, normal: &Vector) -> bool {
+        match self.mode {
+            CullMode::Back => normal[2] <= 0.0,
+            CullMode::Front => normal[2] > 0.0,
+            CullMode::None => false,
+        }
+    }
+
+    pub fn flips(&self, normal: &Vector) -> bool {
+        self.two_sided && normal[2] <= 0.0
+    }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn render_polygons(
     m: &PolygonList, picture: &mut Picture, color: &(usize, usize, usize),
-    shading_mode: &ShadingMode, lighting_config: &LightingConfig, reflection_constants: &ReflectionConstants
+    shading_mode: &ShadingMode, lighting_config: &LightingConfig, reflection_constants: &ReflectionConstants,
+    crease_angle: f32, culling: &Culling,
 ) {
     // for gouraud and phong shading
-    // we need to keep a hash to get the average normal for every polygon that contains this vertex
-    // instead of getting averages we can sum up all the vectors and then normalize it at the end
-    // we need them to be normalized for lighting anyway
-    let mut vertex_normals: HashMap<(isize, isize, isize), Vector> = HashMap::new();
-
-    match shading_mode {
-        ShadingMode::Gouraud | ShadingMode::Phong => {
-            for polygon in m.chunks(3) {
-                let a = [
-                    polygon[1][0] - polygon[0][0],
-                    polygon[1][1] - polygon[0][1],
-                    polygon[1][2] - polygon[0][2],
-                ];
-
-                let b = [
-                    polygon[2][0] - polygon[0][0],
-                    polygon[2][1] - polygon[0][1],
-                    polygon[2][2] - polygon[0][2],
-                ];
-
-                // calculate the normal for backface culling using the cross product of two edges
-                let normal = cross_product(&a, &b);
+    // every corner gets the average normal of the faces around its vertex (unless they're past the crease angle)
+    // since the mesh is indexed we know exactly which faces share a vertex instead of guessing by position
+    let corner_normals = match shading_mode {
+        ShadingMode::Gouraud | ShadingMode::Phong | ShadingMode::Toon(_) | ShadingMode::Normals | ShadingMode::WireframeShaded => m.corner_normals(crease_angle),
+        _ => vec![],
+    };
 
-                for vertex in polygon {
-                    let entry = vertex_normals.entry(vector_to_key(&vertex)).or_insert([0.0, 0.0, 0.0]);
+    // depth shading goes from white at the closest point of the shape to black at the farthest
+    // (the depth pass does the same thing for the whole picture)
+    let (near, far) = m.positions.iter().fold((f32::MIN, f32::MAX), |(near, far), position| (near.max(position[2]), far.min(position[2])));
 
-                    *entry = add_vectors(&entry, &normal);
-                }
-            }
+    // the faces that culling throws away get drawn in red so you can spot flipped triangles
+    // they go first so a face drawn at the same depth afterwards (like one in the mesh twice with opposite windings) ends up on top
+    if matches!(shading_mode, ShadingMode::BackFaces) {
+        for index in 0..m.triangles.len() {
+            let normal = m.face_normal(index);
 
-            for normal in vertex_normals.values_mut() {
-                *normal = normalize_vector(normal);
+            if culling.culls(&normal) {
+                picture.normal = normal;
+                scan_line::flat(picture, &m.triangle(index), &[255.0, 0.0, 0.0]);
             }
         }
-        _ => {}
     }
 
-    for polygon in m.chunks(3) {
-        let a = [
-            polygon[1][0] - polygon[0][0],
-            polygon[1][1] - polygon[0][1],
-            polygon[1][2] - polygon[0][2],
-        ];
-
-        let b = [
-            polygon[2][0] - polygon[0][0],
-            polygon[2][1] - polygon[0][1],
-            polygon[2][2] - polygon[0][2],
-        ];
+    for (index, [a, b, c]) in m.triangles.iter().enumerate() {
+        let polygon = [m.positions[*a], m.positions[*b], m.positions[*c]];
 
         // calculate the normal for backface culling using the cross product of two edges
         // normal = < aybz - azby, azbx - axbz, axby - aybx >
-        let normal: Vector = [
-            a[1] * b[2] - a[2] * b[1],
-            a[2] * b[0] - a[0] * b[2],
-            a[0] * b[1] - a[1] * b[0],
-        ];
+        let normal: Vector = m.face_normal(index);
 
         /*
             if the angle between the normal and the viewer is between -90 and 90, the polygon is facing the viewer
@@ -101,63 +103,149 @@ pub fn render_polygons(
             the dot product of n and v is just the z component of n
         */
 
-        if normal[2] > 0.0 && ENABLE_BACK_FACE_CULLING {
-            match shading_mode {
-                ShadingMode::Wireframe => {
-                    picture.draw_line(
-                        polygon[0][0] as isize, polygon[0][1] as isize, polygon[0][2],
-                        polygon[1][0] as isize, polygon[1][1] as isize, polygon[1][2],
-                        &color,
-                    );
-                    picture.draw_line(
-                        polygon[2][0] as isize, polygon[2][1] as isize, polygon[2][2],
-                        polygon[1][0] as isize, polygon[1][1] as isize, polygon[1][2],
-                        &color,
-                    );
-                    picture.draw_line(
-                        polygon[0][0] as isize, polygon[0][1] as isize, polygon[0][2],
-                        polygon[2][0] as isize, polygon[2][1] as isize, polygon[2][2],
-                        &color,
-                    );
-                },
-                ShadingMode::FlatRandom => {
-                    let mut rng = rand::rng();
-                    scan_line::flat(
-                        picture,
-                        polygon,
-                        &(rng.random::<u8>() as usize, rng.random::<u8>() as usize, rng.random::<u8>() as usize)
-                    );
-                },
-                ShadingMode::Flat => {
-                    scan_line::flat(
-                        picture,
-                        polygon,
-                        &get_illumination(&normalize_vector(&normal), lighting_config, reflection_constants)
-                    );
-                },
-                ShadingMode::Gouraud => {
-                    let normals = [
-                        *vertex_normals.get(&vector_to_key(&polygon[0])).unwrap(),
-                        *vertex_normals.get(&vector_to_key(&polygon[1])).unwrap(),
-                        *vertex_normals.get(&vector_to_key(&polygon[2])).unwrap(),
-                    ];
-
-                    scan_line::gouraud(picture, polygon, normals, lighting_config, reflection_constants);
-                }
-                ShadingMode::Phong => {
-                    let normals = [
-                        *vertex_normals.get(&vector_to_key(&polygon[0])).unwrap(),
-                        *vertex_normals.get(&vector_to_key(&polygon[1])).unwrap(),
-                        *vertex_normals.get(&vector_to_key(&polygon[2])).unwrap(),
-                    ];
-
-                    scan_line::phong(picture, polygon, normals, lighting_config, reflection_constants);
+        if culling.culls(&normal) {
+            continue;
+        }
+
+        // two sided lighting turns the normals around on faces pointing away so they don't just get the ambient light
+        let flip = culling.flips(&normal);
+        let flipped = |normal: Vector| if flip { normal.map(|value| -value) } else { normal };
+        let normal = flipped(normal);
+        let smooth_normals = || corner_normals[index].map(flipped);
+
+        // for the normal pass, phong shading changes it for every pixel
+        picture.normal = normal;
+
+        match shading_mode {
+            ShadingMode::Wireframe => {
+                draw_edges(picture, &polygon, color, 0.0);
+            },
+            ShadingMode::FlatRandom => {
+                let mut rng = rand::rng();
+                scan_line::flat(
+                    picture,
+                    &polygon,
+                    &[rng.random::<u8>() as f32, rng.random::<u8>() as f32, rng.random::<u8>() as f32]
+                );
+            },
+            ShadingMode::Flat | ShadingMode::BackFaces => {
+                // flat shading only has one color for the whole face so we just average the vertex colors
+                let colors = m.corner_colors(index);
+                let average = [
+                    (colors[0][0] + colors[1][0] + colors[2][0]) / 3.0,
+                    (colors[0][1] + colors[1][1] + colors[2][1]) / 3.0,
+                    (colors[0][2] + colors[1][2] + colors[2][2]) / 3.0,
+                ];
+
+                scan_line::flat(
+                    picture,
+                    &polygon,
+                    &apply_vertex_color(get_illumination(&normal, lighting_config, reflection_constants), &average)
+                );
+            },
+            ShadingMode::Gouraud => {
+                scan_line::gouraud(picture, &polygon, smooth_normals(), m.corner_colors(index), lighting_config, reflection_constants);
+            }
+            ShadingMode::Phong => {
+                scan_line::phong(picture, &polygon, smooth_normals(), m.corner_colors(index), lighting_config, reflection_constants, None);
+            }
+            ShadingMode::Toon(bands) => {
+                scan_line::phong(picture, &polygon, smooth_normals(), m.corner_colors(index), lighting_config, reflection_constants, Some(*bands));
+            }
+            ShadingMode::Normals => {
+                // the same colors as the normal pass, x goes to red, y to green, and z to blue
+                scan_line::interpolated(picture, &polygon, smooth_normals(), |_, normal, _| {
+                    normalize_vector(normal).map(|value| (value * 0.5 + 0.5) * 255.0)
+                });
+            }
+            ShadingMode::Depth => {
+                scan_line::interpolated(picture, &polygon, [[]; 3], |_, _, z| {
+                    let gray = if near > far { (z - far) / (near - far) * 255.0 } else { 255.0 };
+                    [gray, gray, gray]
+                });
+            }
+            ShadingMode::UvChecker => {
+                if m.has_uvs() {
+                    scan_line::interpolated(picture, &polygon, [m.uvs[*a], m.uvs[*b], m.uvs[*c]], |_, [u, v], _| {
+                        let square = ((u * UV_CHECKER_SQUARES).floor() + (v * UV_CHECKER_SQUARES).floor()) as i64;
+                        if square.rem_euclid(2) == 0 { [255.0, 255.0, 255.0] } else { [64.0, 64.0, 64.0] }
+                    });
+                } else {
+                    // shapes without uvs stand out in magenta
+                    scan_line::flat(picture, &polygon, &[255.0, 0.0, 255.0]);
                 }
             }
+            ShadingMode::WireframeShaded => {
+                scan_line::phong(picture, &polygon, smooth_normals(), m.corner_colors(index), lighting_config, reflection_constants, None);
+
+                // pulled towards the camera a little so the next face doesn't draw over the lines
+                picture.normal = normal;
+                draw_edges(picture, &polygon, color, 1.0);
+            }
         }
     }
 }
 
+fn draw_edges(picture: &mut Picture, polygon: &[[f32; 4]; 3], color: &(usize, usize, usize), bias: f32) {
+    for (start, end) in [(0, 1), (2, 1), (0, 2)] {
+        picture.draw_line(
+            polygon[start][0] as isize, polygon[start][1] as isize, polygon[start][2] + bias,
+            polygon[end][0] as isize, polygon[end][1] as isize, polygon[end][2] + bias,
+            color,
+        );
+    }
+}
+
+pub fn render_outline(m: &PolygonList, picture: &mut Picture, outline: &Outline) {
+    // silhouettes are edges between a face pointing at the camera and one pointing away
+    // edges with only one face (the border of something open like a plane) count too as long as the face points at the camera
+    // shapes don't always share their vertices (stl files never do) so edges are matched up by position
+    let key = |vertex: usize| m.positions[vertex].map(|value| (value * 1000.0).round() as i64);
+
+    let mut edge_faces: HashMap<([i64; 4], [i64; 4]), Vec<usize>> = HashMap::new();
+    let mut edge_vertices: HashMap<([i64; 4], [i64; 4]), (usize, usize)> = HashMap::new();
+
+    for (face, [a, b, c]) in m.triangles.iter().enumerate() {
+        // triangles squished down to a line or a point (like at the top of a sphere) don't face anywhere
+        if m.face_normal(face) == [0.0, 0.0, 0.0] {
+            continue;
+        }
+
+        for (start, end) in [(*a, *b), (*b, *c), (*c, *a)] {
+            let edge = if key(start) < key(end) { (key(start), key(end)) } else { (key(end), key(start)) };
+            edge_faces.entry(edge).or_default().push(face);
+            edge_vertices.entry(edge).or_insert((start, end));
+        }
+    }
+
+    let threshold = outline.crease_angle.map(|degrees| degrees.to_radians().cos());
+    let unit_normal = |face: usize| normalize_vector(&m.face_normal(face));
+
+    // lines face the camera for the normal pass
+    picture.normal = [0.0, 0.0, 1.0];
+
+    for (edge, faces) in &edge_faces {
+        let front: Vec<bool> = faces.iter().map(|face| m.face_normal(*face)[2] > 0.0).collect();
+
+        let draw = match front.as_slice() {
+            [front] => *front,
+            [first, second] if first != second => true,
+            [true, true] => threshold.is_some_and(|threshold| dot_product(&unit_normal(faces[0]), &unit_normal(faces[1])) < threshold),
+            _ => false,
+        };
+
+        if !draw {
+            continue;
+        }
+
+        // pulled towards the camera a little so the faces next to the line don't cover half of it
+        let (start, end) = edge_vertices[edge];
+        let [x0, y0, z0, _] = m.positions[start];
+        let [x1, y1, z1, _] = m.positions[end];
+        picture.draw_wide_line([x0, y0, z0 + outline.width], [x1, y1, z1 + outline.width], outline.width, &outline.color);
+    }
+}
+
 pub fn add_box(m: &mut PolygonList, x: f32, y: f32, z: f32, w: f32, h: f32, d: f32) {
     /*
         4 ---- 5
@@ -171,95 +259,89 @@ pub fn add_box(m: &mut PolygonList, x: f32, y: f32, z: f32, w: f32, h: f32, d: f
     */
 
     let vertices = [
-        [x, y, z],
-        [x + w, y, z],
-        [x + w, y - h, z],
-        [x, y - h, z],
-        [x, y, z - d],
-        [x + w, y, z - d],
-        [x + w, y - h, z - d],
-        [x, y - h, z - d],
+        m.add_vertex(x, y, z),
+        m.add_vertex(x + w, y, z),
+        m.add_vertex(x + w, y - h, z),
+        m.add_vertex(x, y - h, z),
+        m.add_vertex(x, y, z - d),
+        m.add_vertex(x + w, y, z - d),
+        m.add_vertex(x + w, y - h, z - d),
+        m.add_vertex(x, y - h, z - d),
     ];
 
-    for polygon in CUBE {
-        let (a, b, c) = polygon;
-        add_polygon(m,
-            vertices[a][0], vertices[a][1], vertices[a][2],
-            vertices[b][0], vertices[b][1], vertices[b][2],
-            vertices[c][0], vertices[c][1], vertices[c][2],
-        );
+    for (a, b, c) in CUBE {
+        m.add_triangle(vertices[a], vertices[b], vertices[c]);
     }
 }
 
-pub fn add_sphere(m: &mut PolygonList, cx: f32, cy: f32, cz: f32, r: f32) {
-    let points = generate_sphere_points(cx, cy, cz, r);
+pub fn add_sphere(m: &mut PolygonList, cx: f32, cy: f32, cz: f32, r: f32, steps: i32) {
+    add_ellipsoid(m, [cx, cy, cz], [r, r, r], steps);
+}
+
+pub fn add_ellipsoid(m: &mut PolygonList, center: [f32; 3], radii: [f32; 3], steps: i32) {
+    // a sphere is just an ellipsoid with the same radius in every direction
+    let [cx, cy, cz] = center;
+    let [rx, ry, rz] = radii;
+    let points = generate_ellipsoid_points(cx, cy, cz, rx, ry, rz, steps);
 
-    // we do PARAMETRIC_STEPS + 1 because the semicircle generates one extra point for the south pole the way I coded it
-    // e.g. a PARAMETRIC_STEPS of 10 results in 11 points per semicircle
+    // the poles only get one vertex each, every other latitude is a ring of `steps` vertices
+    // the last longitude wraps back around to the first so the seam is shared as well
+    let north = m.add_vertex(points[0][0], points[0][1], points[0][2]);
+    let south = m.add_vertex(points[1][0], points[1][1], points[1][2]);
+    let first = m.positions.len();
 
-    let get = |longitude: i32, latitude: i32| -> Vector {
-        points[(longitude * (PARAMETRIC_STEPS + 1) + latitude) as usize]
+    for point in &points[2..] {
+        m.add_vertex(point[0], point[1], point[2]);
+    }
+
+    let get = |longitude: i32, latitude: i32| -> usize {
+        if latitude == 0 {
+            north
+        } else if latitude == steps {
+            south
+        } else {
+            first + ((longitude % steps) * (steps - 1) + latitude - 1) as usize
+        }
     };
 
-    for longitude in 0..PARAMETRIC_STEPS {
-        let next = if longitude == PARAMETRIC_STEPS { 0 } else { longitude + 1 };
+    for longitude in 0..steps {
+        let next = longitude + 1;
         // this is for all the polygons that aren't on the poles
-        for latitude in 1..PARAMETRIC_STEPS-1 {
+        for latitude in 1..steps-1 {
             let p1 = get(longitude, latitude);
             let p2 = get(longitude, latitude + 1);
             let p1_across = get(next, latitude);
             let p2_across = get(next, latitude + 1);
 
             // p1, p2, p2_across
-            add_polygon(m,
-                p1[0], p1[1], p1[2],
-                p2[0], p2[1], p2[2],
-                p2_across[0], p2_across[1], p2_across[2],
-            );
+            m.add_triangle(p1, p2, p2_across);
 
             // p1, p2_across, p1_across
-            add_polygon(m,
-                p1[0], p1[1], p1[2],
-                p2_across[0], p2_across[1], p2_across[2],
-                p1_across[0], p1_across[1], p1_across[2],
-            );
+            m.add_triangle(p1, p2_across, p1_across);
         }
         // two triangles at the poles
 
         // pole, p1, p1_across
-        let pole = get(longitude, 0);
-        let p = get(longitude, 1);
-        let p_across = get(next, 1);
-        add_polygon(m,
-            pole[0], pole[1], pole[2],
-            p[0], p[1], p[2],
-            p_across[0], p_across[1], p_across[2],
-        );
+        m.add_triangle(get(longitude, 0), get(longitude, 1), get(next, 1));
 
         // pole, pminus1_across, pminus1
-        let pole = get(longitude, PARAMETRIC_STEPS);
-        let p = get(longitude, PARAMETRIC_STEPS - 1);
-        let p_across = get(next, PARAMETRIC_STEPS - 1);
-        add_polygon(m,
-            pole[0], pole[1], pole[2],
-            p_across[0], p_across[1], p_across[2],
-            p[0], p[1], p[2],
-        );
+        m.add_triangle(get(longitude, steps), get(next, steps - 1), get(longitude, steps - 1));
     }
 }
 
-fn generate_sphere_points(cx: f32, cy: f32, cz: f32, r: f32) -> Vec<Vector> {
-    // not using run_parametric because this parametric is nested but the logic is the same
-    let x = |cir: f32| r * (PI * cir).cos() + cx;
-    let y = |rot: f32, cir: f32| r * (PI * cir).sin() * (2.0 * PI * rot).cos() + cy;
-    let z = |rot: f32, cir: f32| r * (PI * cir).sin() * (2.0 * PI * rot).sin() + cz;
+fn generate_ellipsoid_points(cx: f32, cy: f32, cz: f32, rx: f32, ry: f32, rz: f32, steps: i32) -> Vec<Vector> {
+    // not using sample_parametric because this parametric is nested but the logic is the same
+    let x = |cir: f32| rx * (PI * cir).cos() + cx;
+    let y = |rot: f32, cir: f32| ry * (PI * cir).sin() * (2.0 * PI * rot).cos() + cy;
+    let z = |rot: f32, cir: f32| rz * (PI * cir).sin() * (2.0 * PI * rot).sin() + cz;
 
-    let mut point_list: Vec<Vector> = vec![];
+    // the two poles go first, then each longitude without its poles
+    let mut point_list: Vec<Vector> = vec![[x(0.0), cy, cz], [x(1.0), cy, cz]];
 
-    for i in 0..=PARAMETRIC_STEPS {
-        let rot = i as f32 / PARAMETRIC_STEPS as f32;
-        for j in 0..=PARAMETRIC_STEPS {
-            let cir = j as f32 / PARAMETRIC_STEPS as f32;
+    for i in 0..steps {
+        let rot = i as f32 / steps as f32;
+        for j in 1..steps {
+            let cir = j as f32 / steps as f32;
             point_list.push([x(cir), y(rot, cir), z(rot, cir)]);
         }
     }
@@ -267,54 +349,53 @@ fn generate_sphere_points(cx: f32, cy: f32, cz: f32, r: f32) -> Vec<Vector> {
     point_list
 }
 
-pub fn add_torus(m: &mut PolygonList, cx: f32, cy: f32, cz: f32, r1: f32, r2: f32) {
-    let points = generate_torus_points(cx, cy, cz, r1, r2);
+pub fn add_torus(m: &mut PolygonList, cx: f32, cy: f32, cz: f32, r1: f32, r2: f32, steps: i32) {
+    let points = generate_torus_points(cx, cy, cz, r1, r2, steps);
+    let first = m.positions.len();
+
+    for point in &points {
+        m.add_vertex(point[0], point[1], point[2]);
+    }
+
     // around is which circle of the torus we're currently on
     // on is which part of the circle we're currently on
     // kind of silly names but longitude and latitude didn't make sense so i had to freestyle it
-    // for the torus we can just use PARAMETRIC_STEPS i.e. PARAMETRIC_STEPS of 10 gives 10 points on each circle
-    let get = |around: i32, on: i32| -> Vector {
-        points[(around * (PARAMETRIC_STEPS + 1) + on) as usize]
+    // for the torus we can just use steps i.e. 10 steps gives 10 points on each circle
+    // both directions wrap around so the last circle connects back to the first
+    let get = |around: i32, on: i32| -> usize {
+        first + ((around % steps) * steps + on % steps) as usize
     };
 
-    for around in 0..PARAMETRIC_STEPS {
-        let next = if around == PARAMETRIC_STEPS { 0 } else { around + 1 };
-        for on in 0..PARAMETRIC_STEPS {
+    for around in 0..steps {
+        let next = around + 1;
+        for on in 0..steps {
             let p1 = get(around, on);
             let p2 = get(around, on + 1);
             let p1_across = get(next, on);
             let p2_across = get(next, on + 1);
 
             // p1, p2_across, p2
-            add_polygon(m,
-                p1[0], p1[1], p1[2],
-                p2_across[0], p2_across[1], p2_across[2],
-                p2[0], p2[1], p2[2],
-            );
+            m.add_triangle(p1, p2_across, p2);
 
             // p1, p1_across, p2_across
-            add_polygon(m,
-                p1[0], p1[1], p1[2],
-                p1_across[0], p1_across[1], p1_across[2],
-                p2_across[0], p2_across[1], p2_across[2],
-            );
+            m.add_triangle(p1, p1_across, p2_across);
         }
     }
 }
 
-fn generate_torus_points(cx: f32, cy: f32, cz: f32, r1: f32, r2: f32) -> Vec<Vector> {
+fn generate_torus_points(cx: f32, cy: f32, cz: f32, r1: f32, r2: f32, steps: i32) -> Vec<Vector> {
     // r1 is the radius of the circle that makes up the torus
     // r2 is the radius of the entire torus (translation factor)
     let x = |rot: f32, cir: f32| (2.0 * PI * rot).cos() * (r1 * (2.0 * PI * cir).cos() + r2) + cx;
     let y = |cir: f32| r1 * (2.0 * PI * cir).sin() + cy;
-    let z = |rot: f32, cir: f32| -1.0 * (2.0 * PI * rot).sin() * (r1 * (2.0 * PI * cir).cos() + r2) + cz;
+    let z = |rot: f32, cir: f32| -(2.0 * PI * rot).sin() * (r1 * (2.0 * PI * cir).cos() + r2) + cz;
 
     let mut point_list: Vec<Vector> = vec![];
 
-    for i in 0..=PARAMETRIC_STEPS {
-        let rot = i as f32 / PARAMETRIC_STEPS as f32;
-        for j in 0..=PARAMETRIC_STEPS {
-            let cir = j as f32 / PARAMETRIC_STEPS as f32;
+    for i in 0..steps {
+        let rot = i as f32 / steps as f32;
+        for j in 0..steps {
+            let cir = j as f32 / steps as f32;
             point_list.push([x(rot, cir), y(cir), z(rot, cir)]);
         }
     }
@@ -322,9 +403,16 @@ fn generate_torus_points(cx: f32, cy: f32, cz: f32, r1: f32, r2: f32) -> Vec<Vec
     point_list
 }
 
-pub fn add_cylinder(m: &mut PolygonList, cx: f32, cy: f32, cz: f32, r: f32, h: f32) {
-    let points = generate_cylinder_points(cx, cy, cz, r, h);
-    let length = (PARAMETRIC_STEPS * 2) as usize;
+pub fn add_cylinder(m: &mut PolygonList, cx: f32, cy: f32, cz: f32, r: f32, h: f32, steps: i32) {
+    let points = generate_cylinder_points(cx, cy, cz, r, h, steps);
+    let length = (steps * 2) as usize;
+
+    let first = m.positions.len();
+    for point in &points {
+        m.add_vertex(point[0], point[1], point[2]);
+    }
+    let top_center = m.add_vertex(cx, cy, cz);
+    let bottom_center = m.add_vertex(cx, cy - h, cz);
 
     for i in 0..length {
         if i % 2 == 0 {
@@ -333,65 +421,49 @@ pub fn add_cylinder(m: &mut PolygonList, cx: f32, cy: f32, cz: f32, r: f32, h: f
                 3   1
 
 
-                2   0   
+                2   0
             */
 
-            let p0 = points[i];
-            let p1 = points[((i + 1) % length) as usize];
-            let p2 = points[((i + 2) % length) as usize];
-            let p3 = points[((i + 3) % length) as usize];
+            let p0 = first + i;
+            let p1 = first + (i + 1) % length;
+            let p2 = first + (i + 2) % length;
+            let p3 = first + (i + 3) % length;
 
             // 0 1 2
-            add_polygon(m,
-                p0[0], p0[1], p0[2],
-                p1[0], p1[1], p1[2],
-                p2[0], p2[1], p2[2],
-            );
+            m.add_triangle(p0, p1, p2);
 
             // 1 3 2
-            add_polygon(m,
-                p1[0], p1[1], p1[2],
-                p3[0], p3[1], p3[2],
-                p2[0], p2[1], p2[2],
-            );
+            m.add_triangle(p1, p3, p2);
 
             // bottom from top view
             /*
                 C
-                
+
                     0
                 2
             */
 
             // ccw from bottom 0 2 C
-            add_polygon(m,
-                p0[0], p0[1], p0[2],
-                p2[0], p2[1], p2[2],
-                cx, cy - h, cz,
-            );
+            m.add_triangle(p0, p2, bottom_center);
         } else {
             // top
-            let p1 = points[i];
-            let p3 = points[((i + 2) % length) as usize];
+            let p1 = first + i;
+            let p3 = first + (i + 2) % length;
 
             // top from top view
             /*
                 C
-                
+
                     1
                 3
             */
 
-            add_polygon(m,
-                p1[0], p1[1], p1[2],
-                cx, cy, cz,
-                p3[0], p3[1], p3[2],
-            );
+            m.add_triangle(p1, top_center, p3);
         }
     }
-} 
+}
 
-fn generate_cylinder_points(cx: f32, cy: f32, cz: f32, r: f32, h: f32) -> Vec<Vector> {
+fn generate_cylinder_points(cx: f32, cy: f32, cz: f32, r: f32, h: f32, steps: i32) -> Vec<Vector> {
     // x(t) = rcos(2 * pi * t) + cx
     // z(t) = rsin(2 * pi * t) + cz
     let x = |t: f32| r * (2.0 * PI * t).cos() + cx;
@@ -399,8 +471,8 @@ fn generate_cylinder_points(cx: f32, cy: f32, cz: f32, r: f32, h: f32) -> Vec<Ve
 
     let mut point_list: Vec<Vector> = vec![];
 
-    for i in 0..PARAMETRIC_STEPS {
-        let t = i as f32 / PARAMETRIC_STEPS as f32;
+    for i in 0..steps {
+        let t = i as f32 / steps as f32;
 
         point_list.push([x(t), cy - h, z(t)]);
         point_list.push([x(t), cy, z(t)]);
@@ -409,45 +481,424 @@ fn generate_cylinder_points(cx: f32, cy: f32, cz: f32, r: f32, h: f32) -> Vec<Ve
     point_list
 }
 
-pub fn add_cone(m: &mut PolygonList, cx: f32, cy: f32, cz: f32, r: f32, h: f32) {
-    let points = generate_cone_points(cx, cy, cz, r);
-    let length = PARAMETRIC_STEPS as usize;
-    
+pub fn add_cone(m: &mut PolygonList, cx: f32, cy: f32, cz: f32, r: f32, h: f32, steps: i32) {
+    let points = generate_cone_points(cx, cy, cz, r, steps);
+    let length = steps as usize;
+
+    let first = m.positions.len();
+    for point in &points {
+        m.add_vertex(point[0], point[1], point[2]);
+    }
+    let tip = m.add_vertex(cx, cy + h, cz);
+    let base_center = m.add_vertex(cx, cy, cz);
+
     for i in 0..length {
-        let p0 = points[i];
-        let p1 = points[(i + 1) % length];
-        
+        let p0 = first + i;
+        let p1 = first + (i + 1) % length;
+
         // triangle going to top
         // 0 Ct 1
-        add_polygon(m,
-            p0[0], p0[1], p0[2],
-            cx, cy + h, cz,
-            p1[0], p1[1], p1[2],
-        );
+        m.add_triangle(p0, tip, p1);
 
         // bottom
         // 0 1 Cb
-        add_polygon(m,
-            p0[0], p0[1], p0[2],
-            p1[0], p1[1], p1[2],
-            cx, cy, cz,
-        );
+        m.add_triangle(p0, p1, base_center);
     }
 }
 
-fn generate_cone_points(cx: f32, cy: f32, cz: f32, r: f32) -> Vec<Vector> {
+fn generate_cone_points(cx: f32, cy: f32, cz: f32, r: f32, steps: i32) -> Vec<Vector> {
     // x(t) = rcos(2 * pi * t) + cx
     // z(t) = rsin(2 * pi * t) + cz
     let x = |t: f32| r * (2.0 * PI * t).cos() + cx;
     let z = |t: f32| r * (2.0 * PI * t).sin() + cz;
-    
+
     let mut point_list: Vec<Vector> = vec![];
 
-    for i in 0..PARAMETRIC_STEPS {
-        let t = i as f32 / PARAMETRIC_STEPS as f32;
+    for i in 0..steps {
+        let t = i as f32 / steps as f32;
 
         point_list.push([x(t), cy, z(t)]);
     }
 
     point_list
 }
+
+fn add_revolution(m: &mut PolygonList, center: [f32; 3], profile: &[[f32; 2]], steps: i32, faceted: bool) {
+    // spins a profile of [radius, height] points around the vertical axis through the center
+    // the profile should go from top to bottom so the faces end up pointing outwards
+    // points with a radius of 0 are on the axis so they only get a single vertex instead of a whole ring
+    // every call gets its own vertices, so shapes with hard corners in their profile (like the rim of a prism) spin each piece separately
+    // faceted shapes (like prisms and pyramids) also get separate vertices for every side so smooth shading doesn't round them off
+    let [cx, cy, cz] = center;
+    let steps = steps as usize;
+    let point = |[r, h]: [f32; 2], i: usize| {
+        let t = i as f32 / steps as f32;
+        [r * (2.0 * PI * t).cos() + cx, cy + h, r * (2.0 * PI * t).sin() + cz]
+    };
+
+    let mut rings: Vec<Vec<usize>> = vec![];
+
+    if !faceted {
+        for [r, h] in profile {
+            if *r == 0.0 {
+                let center = m.add_vertex(cx, cy + h, cz);
+                rings.push(vec![center; steps]);
+            } else {
+                rings.push((0..steps).map(|i| {
+                    let [x, y, z] = point([*r, *h], i);
+                    m.add_vertex(x, y, z)
+                }).collect());
+            }
+        }
+    }
+
+    for ring in 0..profile.len().saturating_sub(1) {
+        for i in 0..steps {
+            let next = (i + 1) % steps;
+
+            /*
+                p1 -- p1_next     (ring)
+                |    /  |
+                |   /   |
+                p2 -- p2_next     (ring + 1)
+            */
+
+            let [p1, p1_next, p2, p2_next] = if faceted {
+                [(ring, i), (ring, next), (ring + 1, i), (ring + 1, next)].map(|(ring, i)| {
+                    let [x, y, z] = point(profile[ring], i);
+                    m.add_vertex(x, y, z)
+                })
+            } else {
+                [rings[ring][i], rings[ring][next], rings[ring + 1][i], rings[ring + 1][next]]
+            };
+
+            // next to the axis one of these collapses into a line so we skip it
+            if profile[ring][0] != 0.0 {
+                m.add_triangle(p1, p1_next, p2);
+            }
+            if profile[ring + 1][0] != 0.0 {
+                m.add_triangle(p1_next, p2_next, p2);
+            }
+        }
+    }
+}
+
+pub fn add_capsule(m: &mut PolygonList, cx: f32, cy: f32, cz: f32, r: f32, h: f32, steps: i32) {
+    // a cylinder of height h centered at cxyz with a half sphere on each end
+    // each half sphere gets half as many rings as there are steps around
+    let rings = (steps / 2).max(1);
+    let mut profile: Vec<[f32; 2]> = vec![];
+
+    for i in 0..=rings {
+        let angle = PI / 2.0 * i as f32 / rings as f32;
+        profile.push([r * angle.sin(), h / 2.0 + r * angle.cos()]);
+    }
+
+    for i in 0..=rings {
+        let angle = PI / 2.0 * i as f32 / rings as f32;
+        profile.push([r * angle.cos(), -h / 2.0 - r * angle.sin()]);
+    }
+
+    add_revolution(m, [cx, cy, cz], &profile, steps, false);
+}
+
+pub fn add_frustum(m: &mut PolygonList, center: [f32; 3], r0: f32, r1: f32, h: f32, steps: i32) {
+    // a cone with the top cut off, base radius r0 at the center and top radius r1 h above it
+    // the caps are separate from the side so the rims stay sharp
+    add_revolution(m, center, &[[0.0, h], [r1, h]], steps, false);
+    add_revolution(m, center, &[[r1, h], [r0, 0.0]], steps, false);
+    add_revolution(m, center, &[[r0, 0.0], [0.0, 0.0]], steps, false);
+}
+
+pub fn add_prism(m: &mut PolygonList, cx: f32, cy: f32, cz: f32, r: f32, h: f32, sides: i32) {
+    // base center at cxyz, r is the distance from the center to each corner
+    add_revolution(m, [cx, cy, cz], &[[0.0, h], [r, h]], sides, false);
+    add_revolution(m, [cx, cy, cz], &[[r, h], [r, 0.0]], sides, true);
+    add_revolution(m, [cx, cy, cz], &[[r, 0.0], [0.0, 0.0]], sides, false);
+}
+
+pub fn add_pyramid(m: &mut PolygonList, cx: f32, cy: f32, cz: f32, r: f32, h: f32, sides: i32) {
+    add_revolution(m, [cx, cy, cz], &[[0.0, h], [r, 0.0]], sides, true);
+    add_revolution(m, [cx, cy, cz], &[[r, 0.0], [0.0, 0.0]], sides, false);
+}
+
+pub fn add_disc(m: &mut PolygonList, cx: f32, cy: f32, cz: f32, r0: f32, r1: f32, steps: i32) {
+    // flat ring facing up with outer radius r0 and inner radius r1 (0 for a solid disc)
+    // it has no thickness so it only has a top, use cull none and two_sided to see it from below
+    add_revolution(m, [cx, cy, cz], &[[r1, 0.0], [r0, 0.0]], steps, false);
+}
+
+pub fn add_plane(m: &mut PolygonList, x: f32, y: f32, z: f32, w: f32, d: f32, steps: i32) {
+    // flat grid of steps by steps squares with its corner at xyz, w along x and d back along z like the box
+    // one sided like the disc
+    let steps = steps as usize;
+    let first = m.positions.len();
+
+    for i in 0..=steps {
+        for j in 0..=steps {
+            m.add_vertex(x + w * j as f32 / steps as f32, y, z - d * i as f32 / steps as f32);
+        }
+    }
+
+    let get = |i: usize, j: usize| first + i * (steps + 1) + j;
+
+    for i in 0..steps {
+        for j in 0..steps {
+            // i goes back into the screen and j goes right so this is counterclockwise from above
+            let (a, b, c, d) = (get(i, j), get(i, j + 1), get(i + 1, j + 1), get(i + 1, j));
+            m.add_triangle(a, b, c);
+            m.add_triangle(a, c, d);
+        }
+    }
+}
+
+pub fn add_icosphere(m: &mut PolygonList, cx: f32, cy: f32, cz: f32, r: f32, subdivisions: usize) {
+    // start with an icosahedron and keep splitting every triangle into 4
+    // the new points get pushed back out onto the sphere so it gets rounder each time
+    // unlike the regular sphere every triangle ends up about the same size
+    let golden = (1.0 + 5.0_f32.sqrt()) / 2.0;
+    let mut points: Vec<Vector> = [
+        [-1.0, golden, 0.0], [1.0, golden, 0.0], [-1.0, -golden, 0.0], [1.0, -golden, 0.0],
+        [0.0, -1.0, golden], [0.0, 1.0, golden], [0.0, -1.0, -golden], [0.0, 1.0, -golden],
+        [golden, 0.0, -1.0], [golden, 0.0, 1.0], [-golden, 0.0, -1.0], [-golden, 0.0, 1.0],
+    ].iter().map(normalize_vector).collect();
+
+    let mut faces: Vec<[usize; 3]> = ICOSAHEDRON.iter().map(|(a, b, c)| [*a, *b, *c]).collect();
+
+    for _ in 0..subdivisions {
+        // neighbouring triangles share an edge so we remember the midpoints we already made
+        let mut midpoints: HashMap<(usize, usize), usize> = HashMap::new();
+        let mut midpoint = |a: usize, b: usize, points: &mut Vec<Vector>| -> usize {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                let (p, q) = (points[a], points[b]);
+                points.push(normalize_vector(&[(p[0] + q[0]) / 2.0, (p[1] + q[1]) / 2.0, (p[2] + q[2]) / 2.0]));
+                points.len() - 1
+            })
+        };
+
+        let mut next_faces: Vec<[usize; 3]> = vec![];
+        for [a, b, c] in faces {
+            let ab = midpoint(a, b, &mut points);
+            let bc = midpoint(b, c, &mut points);
+            let ca = midpoint(c, a, &mut points);
+
+            next_faces.push([a, ab, ca]);
+            next_faces.push([b, bc, ab]);
+            next_faces.push([c, ca, bc]);
+            next_faces.push([ab, bc, ca]);
+        }
+        faces = next_faces;
+    }
+
+    let first = m.positions.len();
+    for point in &points {
+        m.add_vertex(point[0] * r + cx, point[1] * r + cy, point[2] * r + cz);
+    }
+
+    for [a, b, c] in faces {
+        m.add_triangle(first + a, first + b, first + c);
+    }
+}
+
+pub fn add_lathe(m: &mut PolygonList, profile: &[[f32; 2]], axis: Rotation, steps: i32) {
+    // spins a 2d profile around the x or y axis
+    // for the y axis x is the distance from the axis and y is the height, for the x axis it's the other way around
+    let mut profile: Vec<[f32; 2]> = profile.iter().map(|[x, y]| match axis {
+        Rotation::X => [y.abs(), *x],
+        _ => [x.abs(), *y],
+    }).collect();
+
+    if profile.len() < 2 {
+        return;
+    }
+
+    // add_revolution wants the profile from top to bottom so the faces point outwards
+    if profile[0][1] < profile[profile.len() - 1][1] {
+        profile.reverse();
+    }
+
+    // close off the ends on the axis so there aren't any holes when culling
+    let top = profile[0];
+    let bottom = profile[profile.len() - 1];
+    if top[0] != 0.0 {
+        profile.insert(0, [0.0, top[1]]);
+    }
+    if bottom[0] != 0.0 {
+        profile.push([0.0, bottom[1]]);
+    }
+
+    let first = m.positions.len();
+    add_revolution(m, [0.0, 0.0, 0.0], &profile, steps, false);
+
+    if let Rotation::X = axis {
+        // it was spun around the y axis so turn it on its side
+        // (x, y, z) -> (y, -x, z) moves the y axis onto the x axis
+        for position in &mut m.positions[first..] {
+            *position = [position[1], -position[0], position[2], position[3]];
+        }
+    }
+}
+
+pub fn add_sweep(m: &mut PolygonList, outline: &[[f32; 2]], path: &[Vector]) {
+    // moves a closed 2d outline along a path and connects each copy to the next one
+    // the outline is laid out on a plane that is perpendicular to the path wherever it is
+    let mut outline = outline.to_vec();
+
+    // points on top of each other would give a tangent with no direction
+    let mut path = path.to_vec();
+    path.dedup_by(|b, a| dot_product(&subtract_vectors(a, b), &subtract_vectors(a, b)) < 1e-8);
+
+    if path.len() < 2 || outline.len() < 3 {
+        return;
+    }
+
+    // the walls and caps assume the outline goes counterclockwise
+    if signed_area(&outline) < 0.0 {
+        outline.reverse();
+    }
+
+    let tangents: Vec<Vector> = (0..path.len()).map(|i| {
+        let before = path[i.saturating_sub(1)];
+        let after = path[(i + 1).min(path.len() - 1)];
+        let tangent = subtract_vectors(&after, &before);
+
+        // a path that doubles back on itself cancels out, so just use the part coming in
+        if dot_product(&tangent, &tangent) < 1e-8 {
+            normalize_vector(&subtract_vectors(&path[i], &before))
+        } else {
+            normalize_vector(&tangent)
+        }
+    }).collect();
+
+    // the outline's y direction starts out as whatever is closest to pointing at the viewer (or up if the path goes straight back)
+    // then every step along the path it gets tilted just enough to stay perpendicular so the outline doesn't twist
+    let mut up = perpendicular(&[0.0, 0.0, 1.0], &tangents[0]).unwrap_or([0.0, 1.0, 0.0]);
+    let slices: Vec<(Vector, Vector, Vector)> = path.iter().zip(&tangents).map(|(point, tangent)| {
+        up = perpendicular(&up, tangent).unwrap_or(up);
+        (*point, cross_product(tangent, &up), up)
+    }).collect();
+
+    let add_vertex = |m: &mut PolygonList, slice: usize, [u, v]: [f32; 2]| {
+        let (point, right, up) = slices[slice];
+        m.add_vertex(
+            point[0] + u * right[0] + v * up[0],
+            point[1] + u * right[1] + v * up[1],
+            point[2] + u * right[2] + v * up[2],
+        )
+    };
+
+    // sharp corners in the outline (like the corners of a box) get a vertex for each wall next to them
+    // so smooth shading only rounds off the parts that came from curves
+    let length = outline.len();
+    let corners: Vec<bool> = (0..length).map(|i| {
+        let [x0, y0] = outline[(i + length - 1) % length];
+        let [x1, y1] = outline[i];
+        let [x2, y2] = outline[(i + 1) % length];
+        let (before, after) = ([x1 - x0, y1 - y0], [x2 - x1, y2 - y1]);
+        let cos = (before[0] * after[0] + before[1] * after[1]) / (before[0].hypot(before[1]) * after[0].hypot(after[1]));
+
+        cos < SWEEP_CORNER_ANGLE.to_radians().cos()
+    }).collect();
+
+    // each slice has the vertex walls use where they start at each outline point and the one where they end
+    let walls: Vec<Vec<(usize, usize)>> = (0..path.len()).map(|slice| {
+        outline.iter().zip(&corners).map(|(point, corner)| {
+            let start = add_vertex(m, slice, *point);
+            let end = if *corner { add_vertex(m, slice, *point) } else { start };
+            (start, end)
+        }).collect()
+    }).collect();
+
+    for slice in 0..path.len() - 1 {
+        for i in 0..length {
+            /*
+                a ---- b     (slice)
+                |  /   |
+                a1 --- b1    (slice + 1)
+            */
+            let (a, b) = (walls[slice][i].0, walls[slice][(i + 1) % length].1);
+            let (a1, b1) = (walls[slice + 1][i].0, walls[slice + 1][(i + 1) % length].1);
+
+            m.add_triangle(a, a1, b);
+            m.add_triangle(b, a1, b1);
+        }
+    }
+
+    // the caps get their own vertices so the edge around them stays sharp
+    // the start cap faces backwards along the path and the end cap faces forwards
+    let last = path.len() - 1;
+    let start_cap: Vec<usize> = outline.iter().map(|point| add_vertex(m, 0, *point)).collect();
+    let end_cap: Vec<usize> = outline.iter().map(|point| add_vertex(m, last, *point)).collect();
+    for [a, b, c] in triangulate(&outline) {
+        m.add_triangle(start_cap[a], start_cap[b], start_cap[c]);
+        m.add_triangle(end_cap[a], end_cap[c], end_cap[b]);
+    }
+}
+
+fn perpendicular(vector: &Vector, axis: &Vector) -> Option<Vector> {
+    // removes the part of vector that goes along axis
+    let along = dot_product(vector, axis);
+    let result = [vector[0] - along * axis[0], vector[1] - along * axis[1], vector[2] - along * axis[2]];
+
+    if dot_product(&result, &result) < 1e-6 {
+        None
+    } else {
+        Some(normalize_vector(&result))
+    }
+}
+
+fn signed_area(outline: &[[f32; 2]]) -> f32 {
+    // shoelace formula, positive when the points go counterclockwise
+    (0..outline.len()).map(|i| {
+        let [x0, y0] = outline[i];
+        let [x1, y1] = outline[(i + 1) % outline.len()];
+        x0 * y1 - x1 * y0
+    }).sum::<f32>() / 2.0
+}
+
+fn triangulate(outline: &[[f32; 2]]) -> Vec<[usize; 3]> {
+    // ear clipping: keep cutting off a corner that sticks out and has nothing else inside it
+    // this works for concave outlines too (letters and such) as long as the outline doesn't cross itself
+    let cross = |a: [f32; 2], b: [f32; 2], c: [f32; 2]| (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]);
+
+    let mut remaining: Vec<usize> = (0..outline.len()).collect();
+    let mut triangles: Vec<[usize; 3]> = vec![];
+
+    while remaining.len() > 3 {
+        let length = remaining.len();
+
+        let ear = (0..length).find(|i| {
+            let (a, b, c) = (remaining[(i + length - 1) % length], remaining[*i], remaining[(i + 1) % length]);
+            let (pa, pb, pc) = (outline[a], outline[b], outline[c]);
+
+            // the corner has to be convex
+            if cross(pa, pb, pc) <= 0.0 {
+                return false;
+            }
+
+            // and no other point can be inside the triangle
+            !remaining.iter().any(|p| {
+                if *p == a || *p == b || *p == c {
+                    return false;
+                }
+                let point = outline[*p];
+                cross(pa, pb, point) >= 0.0 && cross(pb, pc, point) >= 0.0 && cross(pc, pa, point) >= 0.0
+            })
+        });
+
+        match ear {
+            Some(i) => {
+                triangles.push([remaining[(i + length - 1) % length], remaining[i], remaining[(i + 1) % length]]);
+                remaining.remove(i);
+            }
+            None => break,
+        }
+    }
+
+    // if the outline crosses itself we run out of ears, so just fan whatever is left
+    for i in 1..remaining.len().saturating_sub(1) {
+        triangles.push([remaining[0], remaining[i], remaining[i + 1]]);
+    }
+
+    triangles
+}
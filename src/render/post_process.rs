@@ -0,0 +1,298 @@
+use std::{error::Error, fs};
+
+use super::Picture;
+
+// effects that work on the finished picture instead of on shapes
+// they change everything drawn so far so they go after the shapes (and before save), and they stack in the order they're written
+// colors here are the linear ones from the picture where 1.0 is white, tone mapping still happens afterwards when it's saved
+
+pub fn blur(picture: &mut Picture, radius: f32) {
+    let rgba = premultiplied(picture);
+    let blurred = gaussian_blur(&rgba, 4, picture.xres, picture.yres, radius);
+    set_premultiplied(picture, &blurred);
+}
+
+pub fn bloom(picture: &mut Picture, threshold: f32, strength: f32, radius: f32) {
+    // everything brighter than the threshold (in the usual 0-255 units) is blurred and added back on top
+    picture.flatten();
+    let threshold = threshold / 255.0;
+
+    let bright: Vec<f32> = picture.data.chunks(3).flat_map(|pixel| {
+        let brightness = luminance(pixel);
+
+        // only the part above the threshold glows so it fades in instead of popping
+        let scale = if brightness > threshold { (brightness - threshold) / brightness } else { 0.0 };
+        [pixel[0] * scale, pixel[1] * scale, pixel[2] * scale]
+    }).collect();
+
+    let glow = gaussian_blur(&bright, 3, picture.xres, picture.yres, radius);
+
+    for (i, pixel) in glow.chunks(3).enumerate() {
+        for (value, glow) in picture.data[i * 3..i * 3 + 3].iter_mut().zip(pixel) {
+            *value += glow * strength;
+        }
+
+        // glow spilling onto a transparent background should show up too
+        picture.alpha[i] = picture.alpha[i].max((luminance(pixel) * strength).min(1.0));
+    }
+}
+
+pub fn vignette(picture: &mut Picture, strength: f32) {
+    // darkens towards the corners, strength 1 makes the corners black
+    picture.flatten();
+    let center = [picture.xres as f32 / 2.0, picture.yres as f32 / 2.0];
+    let corner = (center[0] * center[0] + center[1] * center[1]).sqrt();
+
+    for y in 0..picture.yres {
+        for x in 0..picture.xres {
+            let distance = ((x as f32 + 0.5 - center[0]).powi(2) + (y as f32 + 0.5 - center[1]).powi(2)).sqrt() / corner;
+            let scale = (1.0 - strength * distance * distance).max(0.0);
+
+            let i = (y * picture.xres + x) * 3;
+            for value in &mut picture.data[i..i + 3] {
+                *value *= scale;
+            }
+        }
+    }
+}
+
+pub fn grade(picture: &mut Picture, brightness: f32, contrast: f32, saturation: f32) {
+    // 1 for all three leaves the picture the same
+    // contrast pushes colors away from (or towards) the middle, saturation away from (or towards) gray
+    picture.flatten();
+
+    for pixel in picture.data.chunks_mut(3) {
+        for value in pixel.iter_mut() {
+            *value = ((*value * brightness - 0.5) * contrast + 0.5).max(0.0);
+        }
+
+        let gray = luminance(pixel);
+        for value in pixel.iter_mut() {
+            *value = (gray + (*value - gray) * saturation).max(0.0);
+        }
+    }
+}
+
+// a 3d color lookup table from a .cube file, every color is replaced by the one the table has for it
+pub struct Lut {
+    size: usize,
+    table: Vec<[f32; 3]>, // red changes the fastest, then green, then blue
+    domain_min: [f32; 3],
+    domain_max: [f32; 3],
+}
+
+pub fn load_lut(file_path: &str) -> Result<Lut, Box<dyn Error>> {
+    // https://kono.phpage.fr/images/a/a1/Adobe-cube-lut-specification-1.0.pdf
+    let text = fs::read_to_string(file_path).map_err(|error| format!("Error reading lut {}: {}", file_path, error))?;
+
+    let mut size = 0;
+    let mut table = vec![];
+    let mut domain_min = [0.0; 3];
+    let mut domain_max = [1.0; 3];
+
+    let parse = |values: &[&str]| -> Result<[f32; 3], Box<dyn Error>> {
+        match values {
+            [r, g, b] => Ok([r.parse()?, g.parse()?, b.parse()?]),
+            _ => Err(format!("Error parsing lut {}: expected 3 numbers but received {}", file_path, values.join(" ")).into()),
+        }
+    };
+
+    for line in text.lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+
+        match parts.as_slice() {
+            [] => {}
+            [first, ..] if first.starts_with('#') => {}
+            ["TITLE", ..] => {}
+            ["LUT_3D_SIZE", value] => size = value.parse().map_err(|_| format!("Error parsing lut size in {}: {}", file_path, value))?,
+            ["LUT_1D_SIZE", ..] => return Err(format!("Cannot load lut {}: only 3d luts are supported.", file_path).into()),
+            ["DOMAIN_MIN", values @ ..] => domain_min = parse(values)?,
+            ["DOMAIN_MAX", values @ ..] => domain_max = parse(values)?,
+            values => table.push(parse(values)?),
+        }
+    }
+
+    if size < 2 || table.len() != size * size * size {
+        return Err(format!("Cannot load lut {}: expected {} colors for a size of {} but found {}.", file_path, size * size * size, size, table.len()).into());
+    }
+
+    Ok(Lut { size, table, domain_min, domain_max })
+}
+
+pub fn apply_lut(picture: &mut Picture, lut: &Lut) {
+    picture.flatten();
+    let last = (lut.size - 1) as f32;
+
+    for pixel in picture.data.chunks_mut(3) {
+        // where the color lands in the table, colors outside the domain use the edge of the table
+        let position: Vec<f32> = (0..3).map(|channel| {
+            let range = lut.domain_max[channel] - lut.domain_min[channel];
+            ((pixel[channel] - lut.domain_min[channel]) / range).clamp(0.0, 1.0) * last
+        }).collect();
+
+        let low: Vec<usize> = position.iter().map(|value| (value.floor() as usize).min(lut.size - 2)).collect();
+        let t: Vec<f32> = position.iter().zip(&low).map(|(value, low)| value - *low as f32).collect();
+
+        // trilinear, so blending the 8 table colors around the position
+        let mut color = [0.0; 3];
+        for corner in 0..8 {
+            let offset = [corner & 1, (corner >> 1) & 1, (corner >> 2) & 1];
+            let weight: f32 = (0..3).map(|channel| if offset[channel] == 1 { t[channel] } else { 1.0 - t[channel] }).product();
+            let index = (low[0] + offset[0]) + (low[1] + offset[1]) * lut.size + (low[2] + offset[2]) * lut.size * lut.size;
+
+            for (value, table_value) in color.iter_mut().zip(lut.table[index]) {
+                *value += table_value * weight;
+            }
+        }
+
+        pixel.copy_from_slice(&color);
+    }
+}
+
+pub fn depth_of_field(picture: &mut Picture, focus: f32, range: f32, radius: f32) {
+    // things at the focus z are sharp and get blurrier until they're range away where they use the full radius
+    // the background counts as really far away
+    // instead of a different blur for every pixel a few blurs are made and each pixel mixes the two closest to what it needs
+    const LEVELS: usize = 4;
+
+    let rgba = premultiplied(picture);
+    let blurs: Vec<Vec<f32>> = (0..=LEVELS)
+        .map(|level| gaussian_blur(&rgba, 4, picture.xres, picture.yres, radius * level as f32 / LEVELS as f32))
+        .collect();
+
+    let mut result = vec![0.0; rgba.len()];
+
+    for y in 0..picture.yres {
+        for x in 0..picture.xres {
+            let amount = match picture.depth(x, y) {
+                Some(z) if range > 0.0 => ((z - focus).abs() / range).min(1.0),
+                Some(z) if z == focus => 0.0,
+                Some(_) => 1.0,
+                None => 1.0,
+            };
+
+            let level = amount * LEVELS as f32;
+            let low = (level.floor() as usize).min(LEVELS - 1);
+            let t = level - low as f32;
+
+            let i = (y * picture.xres + x) * 4;
+            for channel in i..i + 4 {
+                result[channel] = blurs[low][channel] * (1.0 - t) + blurs[low + 1][channel] * t;
+            }
+        }
+    }
+
+    set_premultiplied(picture, &result);
+}
+
+pub fn edges(picture: &mut Picture, threshold: f32, color: &[f32; 3]) {
+    // outlines where the depth or the direction a surface faces suddenly changes (around shapes and on sharp corners)
+    // it's a sobel filter over the same depth and normals the passes use
+    picture.flatten();
+    let (width, height) = (picture.xres, picture.yres);
+
+    let depths: Vec<Option<f32>> = (0..width * height).map(|i| picture.depth(i % width, i / width)).collect();
+    let near = depths.iter().flatten().cloned().fold(f32::NEG_INFINITY, f32::max);
+    let far = depths.iter().flatten().cloned().fold(f32::INFINITY, f32::min);
+
+    // whether anything is there (so the farthest shape still gets outlined against the background),
+    // depth from 0 (farthest) to 1 (closest), and the normal, which are all 0 where nothing is
+    let features: Vec<[f32; 5]> = depths.iter().enumerate().map(|(i, depth)| {
+        let Some(z) = depth else { return [0.0; 5] };
+        let [nx, ny, nz] = picture.surface_normal(i % width, i / width);
+        let length = (nx * nx + ny * ny + nz * nz).sqrt().max(f32::EPSILON);
+        let depth = if near > far { (z - far) / (near - far) } else { 1.0 };
+
+        [1.0, depth, nx / length, ny / length, nz / length]
+    }).collect();
+
+    let color = color.map(|value| value / 255.0);
+    let sample = |x: isize, y: isize| features[y.clamp(0, height as isize - 1) as usize * width + x.clamp(0, width as isize - 1) as usize];
+
+    for y in 0..height as isize {
+        for x in 0..width as isize {
+            let mut strength = 0.0;
+
+            for feature in 0..5 {
+                let value = |dx: isize, dy: isize| sample(x + dx, y + dy)[feature];
+                let gx = value(1, -1) + 2.0 * value(1, 0) + value(1, 1) - value(-1, -1) - 2.0 * value(-1, 0) - value(-1, 1);
+                let gy = value(-1, 1) + 2.0 * value(0, 1) + value(1, 1) - value(-1, -1) - 2.0 * value(0, -1) - value(1, -1);
+                strength += (gx * gx + gy * gy).sqrt();
+            }
+
+            if strength > threshold {
+                let i = y as usize * width + x as usize;
+                picture.data[i * 3..i * 3 + 3].copy_from_slice(&color);
+                picture.alpha[i] = 1.0;
+            }
+        }
+    }
+}
+
+fn luminance(pixel: &[f32]) -> f32 {
+    0.2126 * pixel[0] + 0.7152 * pixel[1] + 0.0722 * pixel[2]
+}
+
+fn premultiplied(picture: &mut Picture) -> Vec<f32> {
+    // blurring colors multiplied by alpha keeps the background color from bleeding into transparent edges
+    picture.flatten();
+
+    picture.data.chunks(3).zip(&picture.alpha).flat_map(|(pixel, alpha)| {
+        [pixel[0] * alpha, pixel[1] * alpha, pixel[2] * alpha, *alpha]
+    }).collect()
+}
+
+fn set_premultiplied(picture: &mut Picture, rgba: &[f32]) {
+    for (i, pixel) in rgba.chunks(4).enumerate() {
+        // completely transparent pixels keep whatever color they had
+        if pixel[3] > 0.0 {
+            for channel in 0..3 {
+                picture.data[i * 3 + channel] = pixel[channel] / pixel[3];
+            }
+        }
+
+        picture.alpha[i] = pixel[3].min(1.0);
+    }
+}
+
+fn gaussian_blur(values: &[f32], channels: usize, width: usize, height: usize, radius: f32) -> Vec<f32> {
+    if radius < 0.5 {
+        return values.to_vec();
+    }
+
+    // the radius is 3 standard deviations since past that the weights are too small to matter
+    let sigma = radius / 3.0;
+    let size = radius.ceil() as isize;
+    let weights: Vec<f32> = (-size..=size).map(|i| (-(i * i) as f32 / (2.0 * sigma * sigma)).exp()).collect();
+    let total: f32 = weights.iter().sum();
+    let kernel: Vec<f32> = weights.iter().map(|weight| weight / total).collect();
+
+    // a gaussian can be done as a horizontal blur then a vertical one which is a lot faster
+    let horizontal = convolve(values, channels, width, height, &kernel, (1, 0));
+    convolve(&horizontal, channels, width, height, &kernel, (0, 1))
+}
+
+fn convolve(values: &[f32], channels: usize, width: usize, height: usize, kernel: &[f32], direction: (isize, isize)) -> Vec<f32> {
+    let size = (kernel.len() / 2) as isize;
+    let mut result = vec![0.0; values.len()];
+
+    for y in 0..height {
+        for x in 0..width {
+            let output = (y * width + x) * channels;
+
+            for (k, weight) in kernel.iter().enumerate() {
+                // past the edge of the picture just uses the edge pixel again
+                let offset = k as isize - size;
+                let sx = (x as isize + offset * direction.0).clamp(0, width as isize - 1) as usize;
+                let sy = (y as isize + offset * direction.1).clamp(0, height as isize - 1) as usize;
+                let input = (sy * width + sx) * channels;
+
+                for channel in 0..channels {
+                    result[output + channel] += values[input + channel] * weight;
+                }
+            }
+        }
+    }
+
+    result
+}
@@ -1,429 +1,189 @@
 use super::{
     Picture,
-    lighting::{LightingConfig, ReflectionConstants, get_illumination},
+    lighting::{LightingConfig, ReflectionConstants, get_illumination, apply_vertex_color, quantize},
 };
 
 type Vector = [f32; 3];
 
-// i'm aware there's a lot of repeating code for all 3 functions,
-// but i wanted each function to be more readable so bear with me
-// (also i was quite confused implementing so i needed to make them separate to understand it myself)
-
-fn draw_scanline(picture: &mut Picture, mut x0: isize, x1: isize, y: isize, mut z0: f32, z1: f32, color: &(usize, usize, usize)) {
-    let step_x = if x0 < x1 { 1 } else { -1 };
-    let dx = (x1 - x0).abs();
-    let step_z = (z1 - z0) / (dx as f32 + 1.0);
-
-    loop {
-        picture.plot(x0, y, z0, color);
-
-        if x0 == x1 { return; }
-
-        x0 += step_x;
-        z0 += step_z;
-    }
-}
+// every way of filling in a triangle ends up in interpolated at the bottom
+// they only differ in what gets interpolated across the triangle and how that turns into a color
 
 pub fn flat(
     picture: &mut Picture,
     polygon: &[[f32; 4]],
-    color: &(usize, usize, usize)
+    color: &Vector
 ) {
-    let p0 = polygon[0];
-    let p1 = polygon[1];
-    let p2 = polygon[2];
-
-    // sort three points by their y values so we have a bottom top and middle
-    let mut b = [p0[0], p0[1], p0[2]];
-    let mut m = [p1[0], p1[1], p1[2]];
-    let mut t = [p2[0], p2[1], p2[2]];
-
-    if b[1] > m[1] {
-        std::mem::swap(&mut b, &mut m);
-    }
-    if m[1] > t[1] {
-        std::mem::swap(&mut m, &mut t);
-    }
-    if b[1] > m[1] {
-        std::mem::swap(&mut b, &mut m);
-    }
-
-    /*
-        scan line conversion works by drawing a bunch of horizontal lines to fill in the polygon
-        lets imagine triangle BMT
-
-            T
-
-                    M
-
-                B
-
-        as our horizontal lines move up from b, we need to figure out a delta x on each side to adjust our endpoints
-        in this case, the left side has a constant delta x which we will call dx0
-        on the right side, BM and MT have different slopes, so we will call them dx1 and dx1_1 respectively
-        we also do the same for the z values
-
-        dx0 = (xt - xb) / (yt - yb)
-        dx1 = (xm - xb) / (ym - yb)
-        dx1_1 = (xt - xm) / (yt - ym)
-
-        dz0 = (zt - zb) / (yt - yb)
-        dz1 = (zm - zb) / (ym - yb)
-        dz1_1 = (zt - zm) / (yt - ym)
-
-        we do have to be careful with triangles that have flat tops and flat bottoms though
-        honestly i'm not entirely sure what i did that fixed it, but i think it has to do with my flip boolean
-        before that my flat bottom triangles would have a weird NaN slope on the right side
-
-        also something REALLY annoying is cumulative floating point error which ends up with a lot of weird artifacts and gaps
-
-        to fix this i kind of spam integer conversion
-    */
-
-    let y_start = b[1] as isize;
-    let y_mid = m[1] as isize;
-    let y_end = t[1] as isize;
-
-    let distance0 = (y_end - y_start) as f32 + 1.0;
-    let distance1 = (y_mid - y_start) as f32 + 1.0;
-    let distance2 = (y_end - y_mid) as f32 + 1.0;
-
-    let dx0 = (t[0] - b[0]) / distance0;
-    let dz0 = (t[2] - b[2]) / distance0;
-    let mut dx1 = (m[0] - b[0]) / distance1;
-    let mut dz1 = (m[2] - b[2]) / distance1;
-
-    let mut x0 = b[0];
-    let mut z0 = b[2];
-    let mut x1 = b[0];
-    let mut z1 = b[2];
-
-    let mut flip = false;
-    let mut y = y_start;
-
-    while y <= y_end {
-        // switch slopes if we pass the middle
-        if !flip && y >= y_mid {
-            flip = true;
-            dx1 = (t[0] - m[0]) / distance2;
-            dz1 = (t[2] - m[2]) / distance2;
-            x1 = m[0];
-            z1 = m[2];
-        }
-
-        draw_scanline(
-            picture,
-            x0 as isize,
-            x1 as isize,
-            y,
-            z0,
-            z1,
-            &color,
-        );
-
-        // increment
-        x0 += dx0;
-        z0 += dz0;
-        x1 += dx1;
-        z1 += dz1;
-        y += 1;
-    }
+    // nothing to interpolate except the depth
+    interpolated(picture, polygon, [[]; 3], |_, _, _| *color);
 }
 
 pub fn gouraud(
     picture: &mut Picture,
     polygon: &[[f32; 4]],
     normals: [Vector; 3],
+    colors: [Vector; 3],
     lighting_config: &LightingConfig,
     reflection_constants: &ReflectionConstants,
 ) {
-    let p0 = polygon[0];
-    let p1 = polygon[1];
-    let p2 = polygon[2];
-
-    let mut b = [p0[0], p0[1], p0[2]];
-    let mut m = [p1[0], p1[1], p1[2]];
-    let mut t = [p2[0], p2[1], p2[2]];
-
-    // we need to sort the colors too
     // the difference between this and phong is that phong we will interpolate by normals instead of colors
-    let mut color_b = get_illumination(&normals[0], lighting_config, reflection_constants);
-    let mut color_m = get_illumination(&normals[1], lighting_config, reflection_constants);
-    let mut color_t = get_illumination(&normals[2], lighting_config, reflection_constants);
+    // vertex colors can just be baked into the lit color since that's what gets interpolated anyway
+    let lit = |corner: usize| apply_vertex_color(get_illumination(&normals[corner], lighting_config, reflection_constants), &colors[corner]);
 
-    if b[1] > m[1] {
-        std::mem::swap(&mut b, &mut m);
-        std::mem::swap(&mut color_b, &mut color_m);
-    }
-    if m[1] > t[1] {
-        std::mem::swap(&mut m, &mut t);
-        std::mem::swap(&mut color_m, &mut color_t);
-    }
-    if b[1] > m[1] {
-        std::mem::swap(&mut b, &mut m);
-        std::mem::swap(&mut color_b, &mut color_m);
-    }
-
-    let y_start = b[1] as isize;
-    let y_mid = m[1] as isize;
-    let y_end = t[1] as isize;
-
-    let distance0 = (y_end - y_start) as f32 + 1.0;
-    let distance1 = (y_mid - y_start) as f32 + 1.0;
-    let distance2 = (y_end - y_mid) as f32 + 1.0;
-
-    let dx0 = (t[0] - b[0]) / distance0;
-    let dz0 = (t[2] - b[2]) / distance0;
-    let mut dx1 = (m[0] - b[0]) / distance1;
-    let mut dz1 = (m[2] - b[2]) / distance1;
-
-    // we need to do the same calculations for colors as we did for the coordinates
-    let dr0 = (color_t.0 as f32 - color_b.0 as f32) / distance0;
-    let dg0 = (color_t.1 as f32 - color_b.1 as f32) / distance0;
-    let db0 = (color_t.2 as f32 - color_b.2 as f32) / distance0;
-
-    let mut dr1 = (color_m.0 as f32 - color_b.0 as f32) / distance1;
-    let mut dg1 = (color_m.1 as f32 - color_b.1 as f32) / distance1;
-    let mut db1 = (color_m.2 as f32 - color_b.2 as f32) / distance1;
-
-    let mut x0 = b[0];
-    let mut z0 = b[2];
-    let mut x1 = b[0];
-    let mut z1 = b[2];
-
-    let mut r0 = color_b.0 as f32;
-    let mut g0 = color_b.1 as f32;
-    let mut b0 = color_b.2 as f32;
-    let mut r1 = color_b.0 as f32;
-    let mut g1 = color_b.1 as f32;
-    let mut b1 = color_b.2 as f32;
-
-    let mut flip = false;
-    let mut y = y_start;
-
-    while y <= y_end {
-        if !flip && y >= y_mid {
-            flip = true;
-            dx1 = (t[0] - m[0]) / distance2;
-            dz1 = (t[2] - m[2]) / distance2;
-            x1 = m[0];
-            z1 = m[2];
-
-            dr1 = (color_t.0 as f32 - color_m.0 as f32) / distance2;
-            dg1 = (color_t.1 as f32 - color_m.1 as f32) / distance2;
-            db1 = (color_t.2 as f32 - color_m.2 as f32) / distance2;
-
-            r1 = color_m.0 as f32;
-            g1 = color_m.1 as f32;
-            b1 = color_m.2 as f32;
-        }
+    interpolated(picture, polygon, [lit(0), lit(1), lit(2)], |_, color, _| *color);
+}
 
-        // we want to draw our horizontal lines but we can't use draw_line because every pixel in the line is different
-        let mut x_start = x0 as isize;
-        let mut x_end = x1 as isize;
-        let mut z_start = z0;
-        let mut z_end = z1;
-        let mut cr_start = r0;
-        let mut cg_start = g0;
-        let mut cb_start = b0;
-        let mut cr_end = r1;
-        let mut cg_end = g1;
-        let mut cb_end = b1;
-
-        // make sure we go left to right since we're going to use a for loop
-        if x_start > x_end {
-            std::mem::swap(&mut x_start, &mut x_end);
-            std::mem::swap(&mut z_start, &mut z_end);
-            std::mem::swap(&mut cr_start, &mut cr_end);
-            std::mem::swap(&mut cg_start, &mut cg_end);
-            std::mem::swap(&mut cb_start, &mut cb_end);
+pub fn phong(
+    picture: &mut Picture,
+    polygon: &[[f32; 4]],
+    normals: [Vector; 3],
+    colors: [Vector; 3],
+    lighting_config: &LightingConfig,
+    reflection_constants: &ReflectionConstants,
+    toon_bands: Option<usize>, // toon shading is the same as phong except the light gets split into bands
+) {
+    // as i said earlier, phong is different than gouraud in that it interpolates by normals
+    // here were don't calculate the color, we will do that at every pixel
+    // vertex colors get interpolated right alongside the normals
+    let corner = |corner: usize| {
+        let [nx, ny, nz] = normals[corner];
+        let [r, g, b] = colors[corner];
+        [nx, ny, nz, r, g, b]
+    };
+
+    interpolated(picture, polygon, [corner(0), corner(1), corner(2)], |picture, [nx, ny, nz, r, g, b], _| {
+        // this time we compute light based on our interpolated normal
+        // (which is also what the normal pass gets instead of the face normal)
+        picture.normal = [*nx, *ny, *nz];
+
+        let mut light = get_illumination(&[*nx, *ny, *nz], lighting_config, reflection_constants);
+        if let Some(bands) = toon_bands {
+            light = quantize(light, bands);
         }
 
-        // calculate our steps for each pixel
-        let distance = (x_end - x_start) as f32 + 1.0;
-        let dz = (z_end - z_start) / distance;
-        let dcr = (cr_end - cr_start) / distance;
-        let dcg = (cg_end - cg_start) / distance;
-        let dcb = (cb_end - cb_start) / distance;
+        apply_vertex_color(light, &[*r, *g, *b])
+    });
+}
 
-        // start drawing the line!
-        let mut z = z_start;
-        let mut cr = cr_start;
-        let mut cg = cg_start;
-        let mut cb = cb_start;
+// vertices get snapped to 1/256 of a pixel instead of whole pixels so small and thin triangles keep their shape
+const SUBPIXEL_BITS: i64 = 8;
+const SUBPIXEL: i64 = 1 << SUBPIXEL_BITS;
+// anything this far off the picture gets pulled in so the edge math can't overflow
+const MAX_COORDINATE: f32 = (1 << 22) as f32;
+
+// what gets interpolated across the triangle
+// everything is divided by w first and multiplied back at each pixel, which is what keeps it from warping with perspective
+// (it doesn't change anything right now since w is always 1, but it would be a pain to go back and fix every shading mode later)
+#[derive(Clone, Copy)]
+struct Varying<const N: usize> {
+    z: f32,
+    inverse_w: f32,
+    values: [f32; N],
+}
 
-        for x in x_start..=x_end {
-            // we use our interpolated colors instead of calculating the color at every pixel
-            // again we use plot not draw_line because every color is different
-            picture.plot(x, y, z, &(
-                cr.clamp(0.0, 255.0) as usize,
-                cg.clamp(0.0, 255.0) as usize,
-                cb.clamp(0.0, 255.0) as usize,
-            ));
-
-            z += dz;
-            cr += dcr;
-            cg += dcg;
-            cb += dcb;
-        }
+impl<const N: usize> Varying<N> {
+    fn new(point: &[f32; 4], values: &[f32; N]) -> Self {
+        let inverse_w = 1.0 / point[3];
+        Self { z: point[2] * inverse_w, inverse_w, values: values.map(|value| value * inverse_w) }
+    }
 
-        // increment
-        x0 += dx0;
-        z0 += dz0;
-        x1 += dx1;
-        z1 += dz1;
+    fn resolve(corners: &[Self; 3], weights: [f32; 3]) -> (f32, [f32; N]) {
+        let blend = |get: &dyn Fn(&Self) -> f32| weights[0] * get(&corners[0]) + weights[1] * get(&corners[1]) + weights[2] * get(&corners[2]);
 
-        r0 += dr0;
-        g0 += dg0;
-        b0 += db0;
-        r1 += dr1;
-        g1 += dg1;
-        b1 += db1;
+        let w = 1.0 / blend(&|corner| corner.inverse_w);
+        let z = blend(&|corner| corner.z) * w;
+        let values = std::array::from_fn(|i| blend(&|corner| corner.values[i]) * w);
 
-        y += 1;
+        (z, values)
     }
 }
 
-pub fn phong(
+fn to_fixed(value: f32) -> i64 {
+    (value.clamp(-MAX_COORDINATE, MAX_COORDINATE) * SUBPIXEL as f32).round() as i64
+}
+
+// twice the area of the triangle a b p, positive when p is to the left of the line going from a to b
+fn edge(a: [i64; 2], b: [i64; 2], p: [i64; 2]) -> i64 {
+    (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
+
+// the top left fill rule: a pixel right on an edge only belongs to the triangle if it's a top or left edge
+// so two triangles sharing an edge never both draw it (or both skip it)
+// with the corners going counterclockwise and y going up, left edges go down and top edges go left
+fn is_top_left(a: [i64; 2], b: [i64; 2]) -> bool {
+    let dx = b[0] - a[0];
+    let dy = b[1] - a[1];
+    dy < 0 || (dy == 0 && dx < 0)
+}
+
+// the shade function turns the values at each pixel (and the depth) into a color
+// it gets the picture too so it can change things like the normal for the passes
+pub fn interpolated<const N: usize>(
     picture: &mut Picture,
     polygon: &[[f32; 4]],
-    normals: [Vector; 3],
-    lighting_config: &LightingConfig,
-    reflection_constants: &ReflectionConstants,
+    values: [[f32; N]; 3],
+    shade: impl Fn(&mut Picture, &[f32; N], f32) -> Vector,
 ) {
-    let p0 = polygon[0];
-    let p1 = polygon[1];
-    let p2 = polygon[2];
+    /*
+        instead of walking down the sides of the triangle and drawing horizontal lines between them
+        (which is what this used to do, and the floating point error from all the adding left gaps and overlaps between triangles)
+        we check every pixel in the box around the triangle and see which side of each edge its middle is on
 
-    let mut b = [p0[0], p0[1], p0[2]];
-    let mut m = [p1[0], p1[1], p1[2]];
-    let mut t = [p2[0], p2[1], p2[2]];
+        for an edge from a to b and a point p:
+        edge = (bx - ax) * (py - ay) - (by - ay) * (px - ax)
 
-    // as i said earlier, phong is different than gouraud in that it interpolates by normals
-    // here were don't calculate the color, we will do that at every pixel
-    let mut n_b = normals[0];
-    let mut n_m = normals[1];
-    let mut n_t = normals[2];
-
-    if b[1] > m[1] {
-        std::mem::swap(&mut b, &mut m);
-        std::mem::swap(&mut n_b, &mut n_m);
-    }
-    if m[1] > t[1] {
-        std::mem::swap(&mut m, &mut t);
-        std::mem::swap(&mut n_m, &mut n_t);
-    }
-    if b[1] > m[1] {
-        std::mem::swap(&mut b, &mut m);
-        std::mem::swap(&mut n_b, &mut n_m);
-    }
+        this is the cross product from the normals, so it's twice the area of the triangle a b p
+        and it's positive if p is on the left side of the edge
+        if the corners go counterclockwise, the inside of the triangle is on the left of all three edges
 
-    let y_start = b[1] as isize;
-    let y_mid = m[1] as isize;
-    let y_end = t[1] as isize;
-    let distance0 = (y_end - y_start) as f32 + 1.0;
-    let distance1 = (y_mid - y_start) as f32 + 1.0;
-    let distance2 = (y_end - y_mid) as f32 + 1.0;
-
-    let dx0 = (t[0] - b[0]) / distance0;
-    let dz0 = (t[2] - b[2]) / distance0;
-    let mut dx1 = (m[0] - b[0]) / distance1;
-    let mut dz1 = (m[2] - b[2]) / distance1;
-
-    // interpolate our normals
-    let dnx0 = (n_t[0] - n_b[0]) / distance0;
-    let dny0 = (n_t[1] - n_b[1]) / distance0;
-    let dnz0 = (n_t[2] - n_b[2]) / distance0;
-
-    let mut dnx1 = (n_m[0] - n_b[0]) / distance1;
-    let mut dny1 = (n_m[1] - n_b[1]) / distance1;
-    let mut dnz1 = (n_m[2] - n_b[2]) / distance1;
-
-    let mut x0 = b[0];
-    let mut z0 = b[2];
-    let mut x1 = b[0];
-    let mut z1 = b[2];
-
-    let mut nx0 = n_b[0];
-    let mut ny0 = n_b[1];
-    let mut nz0 = n_b[2];
-    let mut nx1 = n_b[0];
-    let mut ny1 = n_b[1];
-    let mut nz1 = n_b[2];
-
-    let mut flip = false;
-    let mut y = y_start;
-
-    while y <= y_end {
-        if !flip && y >= y_mid {
-            flip = true;
-            dx1 = (t[0] - m[0]) / distance2;
-            dz1 = (t[2] - m[2]) / distance2;
-            x1 = m[0];
-            z1 = m[2];
-
-            dnx1 = (n_t[0] - n_m[0]) / distance2;
-            dny1 = (n_t[1] - n_m[1]) / distance2;
-            dnz1 = (n_t[2] - n_m[2]) / distance2;
-
-            nx1 = n_m[0];
-            ny1 = n_m[1];
-            nz1 = n_m[2];
-        }
+        the nice part is that each edge value divided by the area of the whole triangle
+        is how much the corner across from that edge counts at p (barycentric coordinates)
+        so interpolating anything is just a weighted average of the corners, no stepping needed
 
-        // once again draw our horizontal lines: same procedure
-        let mut x_start = x0 as isize;
-        let mut x_end = x1 as isize;
-        let mut z_start = z0;
-        let mut z_end = z1;
-        let mut nx_start = nx0;
-        let mut ny_start = ny0;
-        let mut nz_start = nz0;
-        let mut nx_end = nx1;
-        let mut ny_end = ny1;
-        let mut nz_end = nz1;
-
-        if x_start > x_end {
-            std::mem::swap(&mut x_start, &mut x_end);
-            std::mem::swap(&mut z_start, &mut z_end);
-            std::mem::swap(&mut nx_start, &mut nx_end);
-            std::mem::swap(&mut ny_start, &mut ny_end);
-            std::mem::swap(&mut nz_start, &mut nz_end);
-        }
+        the coordinates are integers in 1/256ths of a pixel so the edge values are exact
+        and pixels exactly on an edge can be decided by the fill rule instead of by rounding
+    */
 
-        let distance = (x_end - x_start) as f32 + 1.0;
-        let dz = (z_end - z_start) / distance;
-        let dnx = (nx_end - nx_start) / distance;
-        let dny = (ny_end - ny_start) / distance;
-        let dnz = (nz_end - nz_start) / distance;
+    let mut points = [0, 1, 2].map(|i| [to_fixed(polygon[i][0]), to_fixed(polygon[i][1])]);
+    let mut corners = [0, 1, 2].map(|i| Varying::new(&polygon[i], &values[i]));
 
-        let mut z = z_start;
-        let mut nx = nx_start;
-        let mut ny = ny_start;
-        let mut nz = nz_start;
+    let mut area = edge(points[0], points[1], points[2]);
 
-        for x in x_start..=x_end {
-            // this time we compute light based on our interpolated normal
-            picture.plot(x, y, z, &get_illumination(&[nx, ny, nz], lighting_config, reflection_constants));
+    // squished down to a line so there's nothing to fill
+    if area == 0 {
+        return;
+    }
 
-            z += dz;
-            nx += dnx;
-            ny += dny;
-            nz += dnz;
-        }
+    // clockwise triangles (the ones facing away) get turned around since culling already happened
+    if area < 0 {
+        points.swap(1, 2);
+        corners.swap(1, 2);
+        area = -area;
+    }
 
-        x0 += dx0;
-        z0 += dz0;
-        x1 += dx1;
-        z1 += dz1;
+    // the edge across from each corner
+    let edges = [(1, 2), (2, 0), (0, 1)];
+    let bias = edges.map(|(a, b)| if is_top_left(points[a], points[b]) { 0 } else { -1 });
 
-        nx0 += dnx0;
-        ny0 += dny0;
-        nz0 += dnz0;
-        nx1 += dnx1;
-        ny1 += dny1;
-        nz1 += dnz1;
+    // only the pixels in the box around the triangle that are also on the picture
+    let x_start = (points.iter().map(|point| point[0]).min().unwrap() >> SUBPIXEL_BITS).max(0);
+    let x_end = (points.iter().map(|point| point[0]).max().unwrap() >> SUBPIXEL_BITS).min(picture.xres as i64 - 1);
+    let y_start = (points.iter().map(|point| point[1]).min().unwrap() >> SUBPIXEL_BITS).max(0);
+    let y_end = (points.iter().map(|point| point[1]).max().unwrap() >> SUBPIXEL_BITS).min(picture.yres as i64 - 1);
 
-        y += 1;
+    for y in y_start..=y_end {
+        for x in x_start..=x_end {
+            // the middle of the pixel
+            let p = [x * SUBPIXEL + SUBPIXEL / 2, y * SUBPIXEL + SUBPIXEL / 2];
+            let distances = edges.map(|(a, b)| edge(points[a], points[b], p));
+
+            if distances.iter().zip(bias).any(|(distance, bias)| distance + bias < 0) {
+                continue;
+            }
+
+            let weights = distances.map(|distance| (distance as f64 / area as f64) as f32);
+            let (z, values) = Varying::resolve(&corners, weights);
+            let color = shade(picture, &values, z);
+            picture.plot(x as isize, y as isize, z, &color);
+        }
     }
 }